@@ -0,0 +1,20 @@
+use binhex4::crc::{checksum_bitwise, Crc16};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn crc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crc16");
+    for len in [64, 4 * 1024, 1024 * 1024] {
+        let data: Vec<u8> = (0..len).map(|i| (i * 31 + 7) as u8).collect();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("bitwise", len), &data, |b, data| {
+            b.iter(|| checksum_bitwise(black_box(data)))
+        });
+        group.bench_with_input(BenchmarkId::new("Crc16", len), &data, |b, data| {
+            b.iter(|| Crc16::checksum(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, crc);
+criterion_main!(benches);
@@ -0,0 +1,65 @@
+use binhex4::{
+    decode::{hexbin, DecodeMode},
+    sixbit,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+// the whole decoder and encoder, file by file
+fn files(c: &mut Criterion) {
+    let mut group = c.benchmark_group("files");
+    for (name, bytes) in common::test_files() {
+        let hqx = hexbin(&bytes, true).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("decode", &name), &bytes, |b, bytes| {
+            b.iter(|| hexbin(black_box(bytes), true).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("encode", &name), &hqx, |b, hqx| {
+            b.iter(|| black_box(hqx.borrow()).encode())
+        });
+    }
+    group.finish();
+}
+
+// the 6-bit stage alone, over the characters of every file together
+fn sixbit(c: &mut Criterion) {
+    let chars: Vec<u8> = common::test_files()
+        .iter()
+        .flat_map(|(_, bytes)| {
+            let payload = binhex4::parse::locate(bytes).unwrap();
+            let mut chars = sixbit::strip(payload.encoded, DecodeMode::Lenient);
+            // keep each file's characters whole groups
+            chars.truncate(chars.len() / 4 * 4);
+            chars
+        })
+        .collect();
+    let bytes = sixbit::decode_scalar(&chars).unwrap();
+
+    let mut group = c.benchmark_group("sixbit");
+    group.throughput(Throughput::Bytes(chars.len() as u64));
+    group.bench_function("decode_scalar", |b| {
+        b.iter(|| sixbit::decode_scalar(black_box(&chars)))
+    });
+    group.bench_function("decode", |b| b.iter(|| sixbit::decode(black_box(&chars))));
+    group.bench_function("encode_scalar", |b| {
+        b.iter(|| sixbit::encode_scalar(black_box(&bytes)))
+    });
+    group.bench_function("encode", |b| b.iter(|| sixbit::encode(black_box(&bytes))));
+    group.bench_function("strip", |b| {
+        let files: Vec<_> = common::test_files()
+            .into_iter()
+            .map(|(_, bytes)| bytes)
+            .collect();
+        b.iter(|| {
+            for bytes in &files {
+                black_box(sixbit::strip(bytes, DecodeMode::Lenient));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, files, sixbit);
+criterion_main!(benches);
@@ -11,7 +11,7 @@ fn main() -> std::io::Result<()> {
     let result = result.unwrap();
     let hex_str = result.vec.to_hex(16);
 
-    let mut file = File::create(concat!("./test/raw/earth.out"))?;
+    let mut file = File::create("./test/raw/earth.out")?;
     file.write_all(hex_str.as_bytes())?;
 
     let hqx_ref = result.borrow();
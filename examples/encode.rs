@@ -1,24 +1,20 @@
-use std::{ffi::CString, fs::File, io::Write, path::PathBuf, str::FromStr};
+use std::{fs::File, io::Write, path::PathBuf, str::FromStr};
 
-use binhex4::{encode::binhex, HQXConfig};
+use binhex4::HQX;
 use nom::HexDisplay;
 
 fn main() -> std::io::Result<()> {
     let file_data = include_bytes!(concat!("../test/bin/orange.txt"));
-    let result = binhex(HQXConfig {
-        name: Some(CString::new("orange.txt").unwrap()),
-        file_type: None,
-        author: None,
-        flags: None,
-        data: Some(file_data),
-        resource: None,
-    });
-    assert!(result.is_ok());
+    let result = HQX::builder()
+        .name("orange.txt")
+        .file_type(*b"TEXT")
+        .creator(*b"ttxt")
+        .data_fork(&file_data[..])
+        .build()?;
 
-    let result = result.unwrap();
     let hex_str = result.vec.to_hex(16);
 
-    let mut file = File::create(concat!("./test/raw/orange.out"))?;
+    let mut file = File::create("./test/raw/orange.out")?;
     file.write_all(hex_str.as_bytes())?;
 
     let hqx_ref = result.borrow();
@@ -0,0 +1,289 @@
+use std::{
+    borrow::Cow,
+    ffi::CString,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    encode::binhex,
+    error::{AppleFileError, EncodeError},
+    finder::FinderFlags,
+    fourcc::FourCharCode,
+    sidecar::{from_sidecar, Sidecar, SidecarKind},
+    HQXConfig, HQXRef, HQX,
+};
+
+const SINGLE_MAGIC: u32 = 0x0005_1600;
+const DOUBLE_MAGIC: u32 = 0x0005_1607;
+const VERSION_1: u32 = 0x0001_0000;
+const VERSION_2: u32 = 0x0002_0000;
+const HEADER_LEN: usize = 4 + 4 + 16 + 2;
+const DESCRIPTOR_LEN: usize = 4 + 4 + 4;
+const FINDER_INFO_LEN: usize = 32;
+// version 1 names the file system the file came from; version 2 zeroes it
+const MACINTOSH_HOME_FS: &[u8; 16] = b"Macintosh       ";
+
+pub const DATA_FORK: u32 = 1;
+pub const RESOURCE_FORK: u32 = 2;
+pub const REAL_NAME: u32 = 3;
+pub const COMMENT: u32 = 4;
+pub const FILE_DATES: u32 = 8;
+pub const FINDER_INFO: u32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppleFileKind {
+    Single,
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppleFileVersion {
+    V1,
+    V2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub id: u32,
+    pub data: Cow<'a, [u8]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppleFile<'a> {
+    pub kind: AppleFileKind,
+    pub version: AppleFileVersion,
+    pub home_fs: [u8; 16],
+    pub entries: Vec<Entry<'a>>,
+}
+
+impl<'a> AppleFile<'a> {
+    pub fn new(kind: AppleFileKind, version: AppleFileVersion) -> AppleFile<'a> {
+        AppleFile {
+            kind,
+            version,
+            home_fs: match version {
+                AppleFileVersion::V1 => *MACINTOSH_HOME_FS,
+                AppleFileVersion::V2 => [0; 16],
+            },
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn parse(bytes: &'a [u8]) -> Result<AppleFile<'a>, AppleFileError> {
+        let header = bytes.get(..HEADER_LEN).ok_or(AppleFileError::Truncated)?;
+
+        let kind = match be_u32(header, 0) {
+            SINGLE_MAGIC => AppleFileKind::Single,
+            DOUBLE_MAGIC => AppleFileKind::Double,
+            _ => return Err(AppleFileError::BadMagic),
+        };
+        let version = match be_u32(header, 4) {
+            VERSION_1 => AppleFileVersion::V1,
+            VERSION_2 => AppleFileVersion::V2,
+            version => return Err(AppleFileError::UnsupportedVersion(version)),
+        };
+        let home_fs = header[8..24].try_into().unwrap();
+        let entry_count = u16::from_be_bytes([header[24], header[25]]) as usize;
+
+        let descriptors = bytes
+            .get(HEADER_LEN..HEADER_LEN + entry_count * DESCRIPTOR_LEN)
+            .ok_or(AppleFileError::Truncated)?;
+
+        let entries = descriptors
+            .chunks_exact(DESCRIPTOR_LEN)
+            .map(|descriptor| {
+                let id = be_u32(descriptor, 0);
+                let offset = be_u32(descriptor, 4) as usize;
+                let len = be_u32(descriptor, 8) as usize;
+                let data = bytes
+                    .get(offset..)
+                    .and_then(|rest| rest.get(..len))
+                    .ok_or(AppleFileError::EntryOutOfBounds { id })?;
+                Ok(Entry {
+                    id,
+                    data: Cow::Borrowed(data),
+                })
+            })
+            .collect::<Result<_, AppleFileError>>()?;
+
+        Ok(AppleFile {
+            kind,
+            version,
+            home_fs,
+            entries,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AppleFileError> {
+        let (magic, version) = (
+            match self.kind {
+                AppleFileKind::Single => SINGLE_MAGIC,
+                AppleFileKind::Double => DOUBLE_MAGIC,
+            },
+            match self.version {
+                AppleFileVersion::V1 => VERSION_1,
+                AppleFileVersion::V2 => VERSION_2,
+            },
+        );
+        let entry_count: u16 = self
+            .entries
+            .len()
+            .try_into()
+            .map_err(|_| AppleFileError::TooLarge)?;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&magic.to_be_bytes());
+        bytes.extend_from_slice(&version.to_be_bytes());
+        bytes.extend_from_slice(&self.home_fs);
+        bytes.extend_from_slice(&entry_count.to_be_bytes());
+
+        let mut offset = HEADER_LEN + self.entries.len() * DESCRIPTOR_LEN;
+        for entry in self.entries.iter() {
+            let (entry_offset, len): (u32, u32) =
+                match (offset.try_into(), entry.data.len().try_into()) {
+                    (Ok(entry_offset), Ok(len)) => (entry_offset, len),
+                    _ => return Err(AppleFileError::TooLarge),
+                };
+            bytes.extend_from_slice(&entry.id.to_be_bytes());
+            bytes.extend_from_slice(&entry_offset.to_be_bytes());
+            bytes.extend_from_slice(&len.to_be_bytes());
+            offset += entry.data.len();
+        }
+
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&entry.data);
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn entry(&self, id: u32) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| &entry.data[..])
+    }
+
+    pub fn set_entry(&mut self, id: u32, data: impl Into<Cow<'a, [u8]>>) {
+        let data = data.into();
+        match self.entries.iter_mut().find(|entry| entry.id == id) {
+            Some(entry) => entry.data = data,
+            None => self.entries.push(Entry { id, data }),
+        }
+    }
+
+    pub fn data_fork(&self) -> Option<&[u8]> {
+        self.entry(DATA_FORK)
+    }
+
+    pub fn resource_fork(&self) -> Option<&[u8]> {
+        self.entry(RESOURCE_FORK)
+    }
+
+    pub fn real_name(&self) -> Option<&[u8]> {
+        self.entry(REAL_NAME)
+    }
+
+    // type, creator and Finder flags from the Finder info entry
+    pub fn finder_info(&self) -> Option<(FourCharCode, FourCharCode, FinderFlags)> {
+        let info = self.entry(FINDER_INFO)?.get(..10)?;
+        Some((
+            FourCharCode(info[0..4].try_into().unwrap()),
+            FourCharCode(info[4..8].try_into().unwrap()),
+            FinderFlags::from_bytes(info[8..10].try_into().unwrap()),
+        ))
+    }
+
+    // an AppleDouble file carries no data fork, so it is passed in separately
+    pub fn to_hqx(&self, data_fork: Option<&[u8]>) -> Result<HQX, EncodeError> {
+        let name = self
+            .real_name()
+            .map(CString::new)
+            .transpose()
+            .map_err(|_| EncodeError::BadFileName)?;
+        let (file_type, author, flags) = self.finder_info().unwrap_or_default();
+
+        binhex(HQXConfig {
+            name,
+            file_type: Some(file_type),
+            author: Some(author),
+            flags: Some(flags),
+            data: data_fork.or_else(|| self.data_fork()),
+            resource: self.resource_fork(),
+        })
+    }
+}
+
+impl<'a> HQXRef<'a> {
+    pub fn to_apple_file(&self, kind: AppleFileKind, version: AppleFileVersion) -> AppleFile<'a> {
+        let mut apple_file = AppleFile::new(kind, version);
+
+        apple_file.set_entry(REAL_NAME, self.name.to_bytes());
+
+        let mut finder_info = vec![0; FINDER_INFO_LEN];
+        finder_info[0..4].copy_from_slice(self.file_type.as_bytes());
+        finder_info[4..8].copy_from_slice(self.author.as_bytes());
+        finder_info[8..10].copy_from_slice(&self.flags.to_bytes());
+        apple_file.set_entry(FINDER_INFO, finder_info);
+
+        if kind == AppleFileKind::Single {
+            if let Some(fork) = self.data_fork.as_ref() {
+                apple_file.set_entry(DATA_FORK, fork.data);
+            }
+        }
+        // kept last so that it can grow in place, as Apple's writers do
+        if let Some(fork) = self.resource_fork.as_ref() {
+            apple_file.set_entry(RESOURCE_FORK, fork.data);
+        }
+
+        apple_file
+    }
+
+    // writes the data fork to `path` and everything else to its `._` sidecar
+    pub fn write_apple_double<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let sidecar = apple_double_path(path)?;
+
+        let apple_double = self
+            .to_apple_file(AppleFileKind::Double, AppleFileVersion::V2)
+            .to_bytes()
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+
+        let data = self
+            .data_fork
+            .as_ref()
+            .map(|fork| fork.data)
+            .unwrap_or_default();
+
+        fs::write(path, data)?;
+        fs::write(sidecar, apple_double)
+    }
+}
+
+impl HQX {
+    // reads a file and its `._` AppleDouble sidecar, if there is one, like
+    // `from_path` but ignoring any other sidecar
+    pub fn from_apple_double<P: AsRef<Path>>(path: P) -> io::Result<HQX> {
+        let path = path.as_ref();
+        let sidecar = Sidecar::read(path, SidecarKind::AppleDouble)?.unwrap_or_default();
+        from_sidecar(path, sidecar)
+    }
+}
+
+pub fn apple_double_path<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path has no file name"))?;
+
+    let mut sidecar_name = std::ffi::OsString::from("._");
+    sidecar_name.push(name);
+
+    Ok(path.with_file_name(sidecar_name))
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
@@ -0,0 +1,421 @@
+use std::{
+    ffi::CString,
+    fmt, fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use binhex4::{
+    decode::{hexbin_with_mode, DecodeMode},
+    encode::{EncodeOptions, LineEnding},
+    error::DecodeError,
+    extract::{ExtractOptions, MetadataFormat},
+    filename::FileNameMapper,
+    fourcc::{self, FourCharCode},
+    macroman,
+    verify::verify,
+    HQXConfig, HQXRef, HQX,
+};
+use clap::{Args, Parser, Subcommand};
+
+// exit codes; clap itself exits with 2 on bad usage
+const EXIT_BAD_INPUT: u8 = 1;
+const EXIT_IO: u8 = 3;
+
+const STDIO: &str = "-";
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Encode and decode BinHex 4.0 files",
+    after_help = "Use - for stdin or stdout. Exits with 1 if any input is not valid \
+                  BinHex or fails its CRC check, 2 on bad usage and 3 on I/O errors."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encode files as BinHex 4.0
+    Encode(EncodeArgs),
+    /// Decode the data fork of BinHex 4.0 files
+    Decode(DecodeArgs),
+    /// Print the header, fork sizes and CRC status of BinHex 4.0 files
+    Info(InputArgs),
+    /// Check the CRCs of BinHex 4.0 files
+    Verify(InputArgs),
+    /// Extract BinHex 4.0 files with their resource forks and Finder info
+    Extract(ExtractArgs),
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// Input files
+    #[arg(default_value = STDIO)]
+    files: Vec<PathBuf>,
+    /// Reject stray whitespace and malformed runs
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Args)]
+struct EncodeArgs {
+    /// Input files
+    #[arg(default_value = STDIO)]
+    files: Vec<PathBuf>,
+    /// Output file, or directory when encoding several files
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Resource fork to encode with a single input file
+    #[arg(long)]
+    resource: Option<PathBuf>,
+    /// Name stored in the header; defaults to the input file name
+    #[arg(long)]
+    name: Option<String>,
+    /// File type code, such as TEXT
+    #[arg(long = "type", value_parser = parse_code)]
+    file_type: Option<FourCharCode>,
+    /// Creator code, such as ttxt
+    #[arg(long, value_parser = parse_code)]
+    creator: Option<FourCharCode>,
+    /// Encoded characters per line, or 0 for a single line
+    #[arg(long, default_value_t = 64)]
+    line_length: usize,
+    /// Line ending: cr, lf or crlf
+    #[arg(long, default_value = "crlf", value_parser = parse_line_ending)]
+    line_ending: LineEnding,
+}
+
+#[derive(Args)]
+struct DecodeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Output file, or directory when decoding several files
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Skip CRC verification
+    #[arg(long)]
+    no_verify: bool,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false, id = "format")]
+struct ExtractFormat {
+    /// Write the data fork to NAME and the resource fork to NAME.rsrc
+    #[arg(long)]
+    resource: bool,
+    /// Write the data fork to NAME and everything else to ._NAME
+    #[arg(long)]
+    appledouble: bool,
+    /// Write the data fork to NAME and everything else to .AppleDouble/NAME
+    #[arg(long)]
+    netatalk: bool,
+    /// Write the data fork to NAME and everything else to extended attributes
+    #[arg(long)]
+    xattr: bool,
+    /// Write a MacBinary III file to NAME.bin
+    #[arg(long)]
+    macbinary: bool,
+}
+
+impl ExtractFormat {
+    fn metadata(&self) -> MetadataFormat {
+        if self.resource {
+            MetadataFormat::ResourceFile
+        } else if self.appledouble {
+            MetadataFormat::AppleDouble
+        } else if self.netatalk {
+            MetadataFormat::Netatalk
+        } else if self.xattr {
+            MetadataFormat::ExtendedAttributes
+        } else {
+            MetadataFormat::MacBinary
+        }
+    }
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    format: ExtractFormat,
+    /// Output directory
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+    /// Replace files that already exist
+    #[arg(short, long)]
+    force: bool,
+}
+
+enum Failure {
+    BadInput(String),
+    Io(io::Error),
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::BadInput(_) => EXIT_BAD_INPUT,
+            Failure::Io(_) => EXIT_IO,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::BadInput(msg) => write!(f, "{}", msg),
+            Failure::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Failure {
+        Failure::Io(err)
+    }
+}
+
+impl From<DecodeError> for Failure {
+    fn from(err: DecodeError) -> Failure {
+        match err {
+            DecodeError::Io(err) => Failure::Io(err),
+            err => Failure::BadInput(err.to_string()),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Encode(args) => encode(args),
+        Command::Decode(args) => decode(args),
+        Command::Info(args) => for_each_input(&args, info),
+        // failures are reported by `run_batch`, on stderr
+        Command::Verify(args) => for_each_input(&args, |path, hqx| {
+            verify(hqx)?;
+            println!("{}: ok", path.display());
+            Ok(())
+        }),
+        Command::Extract(args) => extract(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => ExitCode::from(code),
+    }
+}
+
+// runs `f` on every input, reporting failures as they happen and returning
+// the highest exit code once all inputs are done
+fn run_batch<T, F>(inputs: &[T], mut f: F) -> Result<(), u8>
+where
+    T: AsRef<Path>,
+    F: FnMut(&Path) -> Result<(), Failure>,
+{
+    let mut code = 0;
+    for input in inputs {
+        let input = input.as_ref();
+        if let Err(failure) = f(input) {
+            eprintln!("binhex: {}: {}", input.display(), failure);
+            code = code.max(failure.exit_code());
+        }
+    }
+
+    match code {
+        0 => Ok(()),
+        code => Err(code),
+    }
+}
+
+fn for_each_input<F>(args: &InputArgs, mut f: F) -> Result<(), u8>
+where
+    F: FnMut(&Path, &HQX) -> Result<(), Failure>,
+{
+    run_batch(&args.files, |path| {
+        let hqx = read_hqx(path, false, args.strict)?;
+        f(path, &hqx)
+    })
+}
+
+fn encode(args: EncodeArgs) -> Result<(), u8> {
+    let batch = args.files.len() > 1;
+    if batch && (args.resource.is_some() || args.name.is_some()) {
+        eprintln!("binhex: --resource and --name need a single input file");
+        return Err(2);
+    }
+
+    run_batch(&args.files, |path| {
+        let data = read_input(path)?;
+        let resource = args.resource.as_deref().map(fs::read).transpose()?;
+
+        let name = match (&args.name, path.file_name()) {
+            (Some(name), _) => name.clone(),
+            (None, Some(name)) if !is_stdio(path) => name.to_string_lossy().into_owned(),
+            _ => String::from("untitled"),
+        };
+        // characters with no Mac OS Roman equivalent become `_`
+        let name: Vec<u8> = name
+            .chars()
+            .map(|c| macroman::encode_char(c).unwrap_or(b'_'))
+            .collect();
+        let name = CString::new(name).map_err(|err| Failure::BadInput(err.to_string()))?;
+
+        let hqx = HQX::from_config(HQXConfig {
+            name: Some(name),
+            file_type: args.file_type,
+            author: args.creator,
+            flags: None,
+            data: Some(&data),
+            resource: resource.as_deref(),
+        })
+        .map_err(|err| Failure::BadInput(err.to_string()))?;
+        let encoded = hqx.borrow().encode_with(&EncodeOptions {
+            line_length: args.line_length,
+            line_ending: args.line_ending,
+            ..EncodeOptions::default()
+        });
+
+        let output = match &args.output {
+            Some(output) if batch => output.join(hqx_file_name(path)),
+            Some(output) => output.clone(),
+            None if is_stdio(path) => PathBuf::from(STDIO),
+            None => path.with_file_name(hqx_file_name(path)),
+        };
+        write_output(&output, &encoded)
+    })
+}
+
+fn decode(args: DecodeArgs) -> Result<(), u8> {
+    let batch = args.input.files.len() > 1;
+    let mut mapper = FileNameMapper::new();
+
+    run_batch(&args.input.files, |path| {
+        let hqx = read_hqx(path, !args.no_verify, args.input.strict)?;
+        let hqx_ref = hqx.borrow();
+
+        let output = match &args.output {
+            Some(output) if batch || output.is_dir() => mapper.map_in_dir(output, hqx_ref.name),
+            Some(output) => output.clone(),
+            None if is_stdio(path) => PathBuf::from(STDIO),
+            None => mapper.map_in_dir(".", hqx_ref.name),
+        };
+        write_output(&output, data_fork(&hqx_ref))
+    })
+}
+
+fn info(path: &Path, hqx: &HQX) -> Result<(), Failure> {
+    let hqx_ref = hqx.borrow();
+
+    println!("{}:", path.display());
+    println!("  name:     {}", hqx_ref.unicode_name());
+    println!("  type:     {}", hqx_ref.file_type);
+    println!("  creator:  {}", hqx_ref.author);
+    if let Some(kind) = fourcc::lookup(hqx_ref.file_type, hqx_ref.author) {
+        println!("  kind:     {}", kind.mime_type);
+    }
+    println!("  flags:    {:#06X}", hqx_ref.flags.bits());
+    println!("  data:     {} bytes", hqx_ref.data_len);
+    println!("  resource: {} bytes", hqx_ref.resource_len);
+
+    match verify(hqx) {
+        Ok(()) => {
+            println!("  crc:      ok");
+            Ok(())
+        }
+        Err(err) => {
+            println!("  crc:      {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+fn extract(args: ExtractArgs) -> Result<(), u8> {
+    let mut mapper = FileNameMapper::new();
+
+    run_batch(&args.input.files, |path| {
+        let hqx = read_hqx(path, true, args.input.strict)?;
+        let hqx_ref = hqx.borrow();
+
+        let mut options = ExtractOptions {
+            metadata: args.format.metadata(),
+            overwrite: args.force,
+            ..ExtractOptions::default()
+        };
+        // the extracted files are as old as the archive
+        if !is_stdio(path) {
+            options = options.modified_like(path)?;
+        }
+
+        // existing files are refused, or replaced with --force, rather than
+        // renamed around
+        let output = args.output.join(mapper.map(hqx_ref.name));
+        hqx_ref.extract(output, &options)?;
+        Ok(())
+    })
+}
+
+fn read_hqx(path: &Path, should_verify: bool, strict: bool) -> Result<HQX, Failure> {
+    let mode = if strict {
+        DecodeMode::Strict
+    } else {
+        DecodeMode::Lenient
+    };
+    Ok(hexbin_with_mode(&read_input(path)?, should_verify, mode)?)
+}
+
+fn read_input(path: &Path) -> io::Result<Vec<u8>> {
+    if is_stdio(path) {
+        let mut bytes = Vec::new();
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        fs::read(path)
+    }
+}
+
+fn write_output(path: &Path, bytes: &[u8]) -> Result<(), Failure> {
+    if is_stdio(path) {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(bytes)?;
+        stdout.flush()?;
+    } else {
+        fs::write(path, bytes)?;
+    }
+    Ok(())
+}
+
+fn data_fork<'a>(hqx_ref: &HQXRef<'a>) -> &'a [u8] {
+    hqx_ref
+        .data_fork
+        .as_ref()
+        .map(|fork| fork.data)
+        .unwrap_or_default()
+}
+
+fn hqx_file_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    format!("{}.hqx", name)
+}
+
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO
+}
+
+fn parse_code(s: &str) -> Result<FourCharCode, String> {
+    s.parse().map_err(|err| format!("{}", err))
+}
+
+fn parse_line_ending(s: &str) -> Result<LineEnding, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "cr" => Ok(LineEnding::Cr),
+        "lf" => Ok(LineEnding::Lf),
+        "crlf" => Ok(LineEnding::CrLf),
+        _ => Err(String::from("expected cr, lf or crlf")),
+    }
+}
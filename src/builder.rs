@@ -0,0 +1,159 @@
+use std::{borrow::Cow, ffi::CString};
+
+use crate::{
+    encode::binhex, error::BuildError, finder::FinderFlags, fourcc::FourCharCode, macroman,
+    HQXConfig, HQX,
+};
+
+// longest name the header's length byte can hold
+const MAX_NAME_LEN: usize = 255;
+// longest name an HFS volume accepts
+const MAX_HFS_NAME_LEN: usize = 63;
+
+#[derive(Debug, Clone)]
+enum Name<'a> {
+    Text(Cow<'a, str>),
+    MacRoman(Cow<'a, [u8]>),
+}
+
+// builds an `HQX` from owned or borrowed parts, checking the name and fork
+// sizes before anything is encoded
+#[derive(Debug, Clone)]
+pub struct HqxBuilder<'a> {
+    name: Option<Name<'a>>,
+    file_type: FourCharCode,
+    creator: FourCharCode,
+    finder_flags: FinderFlags,
+    data_fork: Cow<'a, [u8]>,
+    resource_fork: Cow<'a, [u8]>,
+    hfs_name_limit: bool,
+}
+
+impl<'a> Default for HqxBuilder<'a> {
+    fn default() -> HqxBuilder<'a> {
+        HqxBuilder {
+            name: None,
+            file_type: FourCharCode::default(),
+            creator: FourCharCode::default(),
+            finder_flags: FinderFlags::default(),
+            data_fork: Cow::Borrowed(&[]),
+            resource_fork: Cow::Borrowed(&[]),
+            hfs_name_limit: true,
+        }
+    }
+}
+
+impl<'a> HqxBuilder<'a> {
+    pub fn new() -> HqxBuilder<'a> {
+        HqxBuilder::default()
+    }
+
+    // a name that is converted to Mac OS Roman when built
+    pub fn name(mut self, name: impl Into<Cow<'a, str>>) -> HqxBuilder<'a> {
+        self.name = Some(Name::Text(name.into()));
+        self
+    }
+
+    // a name that is already in Mac OS Roman, such as one from another file
+    pub fn mac_roman_name(mut self, name: impl Into<Cow<'a, [u8]>>) -> HqxBuilder<'a> {
+        self.name = Some(Name::MacRoman(name.into()));
+        self
+    }
+
+    pub fn file_type(mut self, file_type: impl Into<FourCharCode>) -> HqxBuilder<'a> {
+        self.file_type = file_type.into();
+        self
+    }
+
+    pub fn creator(mut self, creator: impl Into<FourCharCode>) -> HqxBuilder<'a> {
+        self.creator = creator.into();
+        self
+    }
+
+    pub fn finder_flags(mut self, flags: impl Into<FinderFlags>) -> HqxBuilder<'a> {
+        self.finder_flags = flags.into();
+        self
+    }
+
+    pub fn data_fork(mut self, data: impl Into<Cow<'a, [u8]>>) -> HqxBuilder<'a> {
+        self.data_fork = data.into();
+        self
+    }
+
+    pub fn resource_fork(mut self, resource: impl Into<Cow<'a, [u8]>>) -> HqxBuilder<'a> {
+        self.resource_fork = resource.into();
+        self
+    }
+
+    // whether to hold names to the 63 characters HFS allows (the default),
+    // rather than only to the 255 bytes the header allows
+    pub fn hfs_name_limit(mut self, enabled: bool) -> HqxBuilder<'a> {
+        self.hfs_name_limit = enabled;
+        self
+    }
+
+    pub fn build(&self) -> Result<HQX, BuildError> {
+        let name = self
+            .name
+            .as_ref()
+            .map(|name| self.check_name(name))
+            .transpose()?;
+
+        if u32::try_from(self.data_fork.len()).is_err() {
+            return Err(BuildError::DataForkTooLarge {
+                len: self.data_fork.len(),
+            });
+        }
+        if u32::try_from(self.resource_fork.len()).is_err() {
+            return Err(BuildError::ResourceForkTooLarge {
+                len: self.resource_fork.len(),
+            });
+        }
+
+        let hqx = binhex(HQXConfig {
+            name,
+            file_type: Some(self.file_type),
+            author: Some(self.creator),
+            flags: Some(self.finder_flags),
+            data: Some(&self.data_fork),
+            resource: Some(&self.resource_fork),
+        })
+        .expect("the builder checks everything binhex rejects");
+
+        Ok(hqx)
+    }
+
+    fn check_name(&self, name: &Name) -> Result<CString, BuildError> {
+        let bytes = match name {
+            Name::Text(text) => {
+                let mut bytes = Vec::with_capacity(text.len());
+                for character in text.chars() {
+                    let byte = macroman::encode_char(character)
+                        .ok_or(BuildError::NameNotMacRoman { character })?;
+                    bytes.push(byte);
+                }
+                bytes
+            }
+            Name::MacRoman(bytes) => bytes.to_vec(),
+        };
+
+        // one byte per character, so byte offsets and lengths are characters
+        let len = bytes.len();
+        if len == 0 {
+            return Err(BuildError::NameEmpty);
+        }
+        if len > MAX_NAME_LEN {
+            return Err(BuildError::NameTooLong { len });
+        }
+        if self.hfs_name_limit && len > MAX_HFS_NAME_LEN {
+            return Err(BuildError::NameTooLongForHfs { len });
+        }
+        if let Some(offset) = bytes.iter().position(|b| *b == b':') {
+            return Err(BuildError::NameContainsColon { offset });
+        }
+
+        CString::new(bytes).map_err(|err| BuildError::NameContainsNul {
+            offset: err.nul_position(),
+        })
+    }
+}
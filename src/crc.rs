@@ -0,0 +1,206 @@
+// CRC-16/XMODEM, used by BinHex 4.0 and MacBinary II: polynomial 0x1021, no
+// reflection, zero initial value and no final xor
+const POLY: u16 = 0x1021;
+
+// below this many bytes the table is faster than setting up the clmul path
+#[cfg(all(feature = "clmul", target_arch = "x86_64"))]
+const CLMUL_MIN_LEN: usize = 128;
+
+// `TABLES[k][b]` is the crc of byte `b` followed by `k` zero bytes, so that
+// eight bytes can be folded in with eight independent lookups
+static TABLES: [[u16; 256]; 8] = make_tables();
+
+const fn make_tables() -> [[u16; 256]; 8] {
+    let mut tables = [[0; 256]; 8];
+
+    let mut b = 0;
+    while b < 256 {
+        let mut crc = (b as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ POLY
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        tables[0][b] = crc;
+        b += 1;
+    }
+
+    let mut k = 1;
+    while k < 8 {
+        let mut b = 0;
+        while b < 256 {
+            let previous = tables[k - 1][b];
+            tables[k][b] = (previous << 8) ^ tables[0][(previous >> 8) as usize];
+            b += 1;
+        }
+        k += 1;
+    }
+
+    tables
+}
+
+// an incremental crc; unlike the bitwise form this crate used to compute,
+// `finish` needs no zero bytes fed in place of the crc
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Crc16 {
+    crc: u16,
+}
+
+impl Crc16 {
+    pub const fn new() -> Crc16 {
+        Crc16 { crc: 0 }
+    }
+
+    pub fn checksum(data: &[u8]) -> u16 {
+        let mut crc = Crc16::new();
+        crc.update(data);
+        crc.finish()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        #[cfg(all(feature = "clmul", target_arch = "x86_64"))]
+        if data.len() >= CLMUL_MIN_LEN && clmul::is_supported() {
+            // safety: the cpu supports the instructions `update` is compiled for
+            self.crc = unsafe { clmul::update(self.crc, data) };
+            return;
+        }
+
+        self.crc = update_slice_by_8(self.crc, data);
+    }
+
+    pub const fn finish(&self) -> u16 {
+        self.crc
+    }
+}
+
+// the original bit at a time implementation, kept as a reference for tests
+// and benchmarks
+pub fn checksum_bitwise(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in data.iter().copied().chain([0, 0]) {
+        let mut byte = byte;
+        for _ in 0..8 {
+            // 0xFFFF if the most significant bit of crc is set, 0 otherwise
+            let cond = (((crc & 0x8000) as i16) >> 15) as u16;
+            crc = (crc << 1) | ((byte >> 7) as u16);
+            crc ^= cond & POLY;
+            byte <<= 1;
+        }
+    }
+    crc
+}
+
+fn update_slice_by_8(mut crc: u16, data: &[u8]) -> u16 {
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let [hi, lo] = crc.to_be_bytes();
+        crc = TABLES[7][(chunk[0] ^ hi) as usize]
+            ^ TABLES[6][(chunk[1] ^ lo) as usize]
+            ^ TABLES[5][chunk[2] as usize]
+            ^ TABLES[4][chunk[3] as usize]
+            ^ TABLES[3][chunk[4] as usize]
+            ^ TABLES[2][chunk[5] as usize]
+            ^ TABLES[1][chunk[6] as usize]
+            ^ TABLES[0][chunk[7] as usize];
+    }
+    update_bytewise(crc, chunks.remainder())
+}
+
+fn update_bytewise(mut crc: u16, data: &[u8]) -> u16 {
+    for byte in data {
+        crc = (crc << 8) ^ TABLES[0][((crc >> 8) as u8 ^ byte) as usize];
+    }
+    crc
+}
+
+// folds 64 bytes at a time with carry-less multiplication, keeping four
+// 128-bit lanes that are each congruent, modulo the polynomial, to the part
+// of the message they have absorbed
+#[cfg(all(feature = "clmul", target_arch = "x86_64"))]
+mod clmul {
+    use std::arch::x86_64::*;
+
+    use super::update_slice_by_8;
+
+    // x^n mod P, with P = x^16 + 0x1021
+    const fn x_pow_mod(n: u32) -> u64 {
+        let mut r: u32 = 1;
+        let mut i = 0;
+        while i < n {
+            r <<= 1;
+            if r & 0x1_0000 != 0 {
+                r ^= 0x1_1021;
+            }
+            i += 1;
+        }
+        r as u64
+    }
+
+    // constants that move the high and low halves of a lane forward by 128
+    // and 512 bits
+    const FOLD_128: (u64, u64) = (x_pow_mod(128 + 64), x_pow_mod(128));
+    const FOLD_512: (u64, u64) = (x_pow_mod(512 + 64), x_pow_mod(512));
+
+    pub(super) fn is_supported() -> bool {
+        is_x86_feature_detected!("pclmulqdq") && is_x86_feature_detected!("ssse3")
+    }
+
+    #[target_feature(enable = "pclmulqdq,ssse3,sse2")]
+    pub(super) unsafe fn update(crc: u16, data: &[u8]) -> u16 {
+        debug_assert!(data.len() >= 64);
+
+        // loads a chunk so that its first byte is the most significant
+        let reverse = _mm_set_epi8(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+        let load = |chunk: &[u8]| {
+            _mm_shuffle_epi8(_mm_loadu_si128(chunk.as_ptr() as *const __m128i), reverse)
+        };
+        let fold = |lane: __m128i, (hi, lo): (u64, u64)| {
+            let constants = _mm_set_epi64x(hi as i64, lo as i64);
+            _mm_xor_si128(
+                _mm_clmulepi64_si128(lane, constants, 0x11),
+                _mm_clmulepi64_si128(lane, constants, 0x00),
+            )
+        };
+
+        let mut blocks = data.chunks_exact(64);
+        let first = blocks.next().unwrap();
+        let mut lanes = [
+            load(&first[0..16]),
+            load(&first[16..32]),
+            load(&first[32..48]),
+            load(&first[48..64]),
+        ];
+        // the running crc stands in for the first two bytes of the message
+        lanes[0] = _mm_xor_si128(lanes[0], _mm_set_epi64x(((crc as u64) << 48) as i64, 0));
+
+        for block in &mut blocks {
+            for (i, lane) in lanes.iter_mut().enumerate() {
+                let chunk = load(&block[i * 16..i * 16 + 16]);
+                *lane = _mm_xor_si128(fold(*lane, FOLD_512), chunk);
+            }
+        }
+
+        let mut folded = lanes[0];
+        for lane in &lanes[1..] {
+            folded = _mm_xor_si128(fold(folded, FOLD_128), *lane);
+        }
+
+        let rest = blocks.remainder();
+        let mut chunks = rest.chunks_exact(16);
+        for chunk in &mut chunks {
+            folded = _mm_xor_si128(fold(folded, FOLD_128), load(chunk));
+        }
+
+        // the crc of the folded lane followed by the leftover bytes is the
+        // crc of the whole message
+        let mut bytes = [0u8; 16];
+        _mm_storeu_si128(bytes.as_mut_ptr() as *mut __m128i, folded);
+        bytes.reverse();
+        let crc = update_slice_by_8(0, &bytes);
+        update_slice_by_8(crc, chunks.remainder())
+    }
+}
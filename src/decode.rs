@@ -1,150 +1,262 @@
 use crate::error::DecodeError;
+use crate::legacy;
+use crate::macbinary::MacBinary;
+use crate::parse::{detect, find_marker, locate, Format, MARKER};
+use crate::rle::RleDecoder;
+use crate::sixbit::{self, is_skipped, DECODINGS, INVALID};
 use crate::verify::verify;
-use crate::{parse::parse, HQX};
-use lazy_static::lazy_static;
+use crate::HQX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    // skips stray whitespace and 0xFF bytes, and accepts malformed runs
+    #[default]
+    Lenient,
+    // allows only line breaks between encoded characters, and rejects runs
+    // with nothing to repeat or a marker with no count
+    Strict,
+}
 
 pub fn hexbin(i: &[u8], should_verify: bool) -> Result<HQX, DecodeError> {
-    match parse(i) {
-        Ok((_, encoded_bin_with_newlines)) => {
-            let hqx = decode(encoded_bin_with_newlines);
-            if should_verify {
-                verify(&hqx)?;
-            }
-            Ok(hqx)
-        }
-        Err(_) => Err(DecodeError::BadFormat),
-    }
+    hexbin_with_mode(i, should_verify, DecodeMode::default())
 }
 
-lazy_static! {
-    static ref BYTE_DECODINGS: [u8; 82] = create_byte_decodings();
+pub fn hexbin_with_mode(
+    i: &[u8],
+    should_verify: bool,
+    mode: DecodeMode,
+) -> Result<HQX, DecodeError> {
+    hexbin_from(i, 0, CharDecoder::new(mode), should_verify)
 }
 
-fn create_byte_decodings() -> [u8; 82] {
-    let mut byte_decodings = [0; 82];
+// decodes the first archive at or after `from`, in whichever format `detect`
+// finds there; `chars` must already be positioned at `from` so that errors
+// report positions within all of `i`
+fn hexbin_from(
+    i: &[u8],
+    from: usize,
+    mut chars: CharDecoder,
+    should_verify: bool,
+) -> Result<HQX, DecodeError> {
+    let format = detect(&i[from..]).ok_or(DecodeError::BadFormat)?;
+    if let Format::Hex | Format::CompressedHex = format {
+        return legacy::hexbin(&i[from..], should_verify).map_err(|err| chars.offset_error(err));
+    }
 
-    let mut next = 0usize;
+    let payload = locate(&i[from..]).ok_or(DecodeError::BadFormat)?;
+    // track positions from the start of the input for error reporting
+    let start = from + payload.offset + 1;
+    chars.advance_over(&i[from..start]);
 
-    macro_rules! fill_byte_encoding_range {
-        ($items:expr) => {
-            let items = $items;
-            for (i, byte) in items.iter().enumerate() {
-                byte_decodings[(*byte - b'!') as usize] = (i + next) as u8;
-            }
-            next += items.len();
-        };
+    let hqx = decode(payload.encoded, chars)?;
+    if format == Format::BinHex5 {
+        return unwrap_macbinary(hqx, should_verify);
+    }
+    // make sure the header and forks are in bounds even when not verifying
+    hqx.try_borrow()?;
+    if should_verify {
+        verify(&hqx)?;
     }
+    Ok(hqx)
+}
 
-    // [0-12] !"#$%&'()*+,-
-    fill_byte_encoding_range!([
-        b'!', b'"', b'#', b'$', b'%', b'&', b'\'', b'(', b')', b'*', b'+', b',', b'-'
-    ]);
+// BinHex 5.0 encodes a MacBinary file either on its own or as the data fork
+// of a 4.0 header
+fn unwrap_macbinary(hqx: HQX, should_verify: bool) -> Result<HQX, DecodeError> {
+    if let Ok(macbinary) = MacBinary::parse(&hqx.vec) {
+        return macbinary.to_hqx().map_err(|_| DecodeError::BadFormat);
+    }
 
-    // [13-14]
-    // [15-21] 0123456
-    fill_byte_encoding_range!([b'0', b'1', b'2', b'3', b'4', b'5', b'6']);
+    let hqx_ref = hqx.try_borrow()?;
+    if should_verify {
+        verify(&hqx)?;
+    }
+    let unwrapped = match (&hqx_ref.data_fork, &hqx_ref.resource_fork) {
+        (Some(fork), None) => MacBinary::parse(fork.data)
+            .ok()
+            .map(|macbinary| macbinary.to_hqx().map_err(|_| DecodeError::BadFormat)),
+        _ => None,
+    };
+    unwrapped.unwrap_or(Ok(hqx))
+}
 
-    // [22]
-    // [23-24] 89
-    fill_byte_encoding_range!([b'8', b'9']);
+// decodes every archive in `i`, such as the attachments saved in a mailbox,
+// yielding the byte offset each one starts at along with the result
+pub fn hexbin_all(i: &[u8], should_verify: bool) -> HexbinAll<'_> {
+    hexbin_all_with_mode(i, should_verify, DecodeMode::default())
+}
 
-    // [25-30]
-    // [31-45] @ABCDEFGHIJKLMN
-    fill_byte_encoding_range!([
-        b'@', b'A', b'B', b'C', b'D', b'E', b'F', b'G', b'H', b'I', b'J', b'K', b'L', b'M', b'N'
-    ]);
+pub fn hexbin_all_with_mode(i: &[u8], should_verify: bool, mode: DecodeMode) -> HexbinAll<'_> {
+    HexbinAll {
+        input: i,
+        next: find_marker(i, 0).or(Some(0)),
+        chars: CharDecoder::new(mode),
+        position: 0,
+        should_verify,
+    }
+}
 
-    // [46]
-    // [47-53] PQRSTUV
-    fill_byte_encoding_range!([b'P', b'Q', b'R', b'S', b'T', b'U', b'V']);
+pub struct HexbinAll<'a> {
+    input: &'a [u8],
+    next: Option<usize>,
+    // positioned at `position`, the start of the last archive
+    chars: CharDecoder,
+    position: usize,
+    should_verify: bool,
+}
 
-    // [54]
-    // [55-58] XYZ[
-    fill_byte_encoding_range!([b'X', b'Y', b'Z', b'[']);
+impl<'a> Iterator for HexbinAll<'a> {
+    type Item = (usize, Result<HQX, DecodeError>);
 
-    // [59-62]
-    // [63-69]`abcdef
-    fill_byte_encoding_range!([b'`', b'a', b'b', b'c', b'd', b'e', b'f']);
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next?;
+        // each archive runs up to the next marker, so a damaged one cannot
+        // swallow the archives after it
+        let following = find_marker(self.input, start + MARKER.len());
+        let end = following.unwrap_or(self.input.len());
+        self.next = following;
 
-    // [70]
-    // [71-76] hijklm
-    fill_byte_encoding_range!([b'h', b'i', b'j', b'k', b'l', b'm']);
+        self.chars.advance_over(&self.input[self.position..start]);
+        self.position = start;
 
-    // [77-78]
-    // [79-81] pqr
-    fill_byte_encoding_range!([b'p', b'q', b'r']);
+        let input = &self.input[..end];
+        let result = hexbin_from(input, start, self.chars.clone(), self.should_verify);
+        Some((start, result))
+    }
+}
 
-    debug_assert_eq!(next, 64);
+pub(crate) fn decode_char(val: u8) -> Option<u8> {
+    Some(DECODINGS[val as usize]).filter(|b| *b != INVALID)
+}
 
-    byte_decodings
+// decodes characters of the encoded stream while tracking their position
+#[derive(Debug, Clone)]
+pub(crate) struct CharDecoder {
+    mode: DecodeMode,
+    line: usize,
+    column: usize,
+    after_cr: bool,
 }
 
-const RLE_MARKER_BYTE: u8 = 0x90;
-const EOF: u8 = 0xFF;
+impl CharDecoder {
+    pub(crate) fn new(mode: DecodeMode) -> CharDecoder {
+        CharDecoder {
+            mode,
+            line: 1,
+            column: 1,
+            after_cr: false,
+        }
+    }
 
-fn decode(encoded_bin_with_newlines: &[u8]) -> HQX {
-    let mut out = Vec::<u8>::with_capacity(encoded_bin_with_newlines.len());
+    pub(crate) fn mode(&self) -> DecodeMode {
+        self.mode
+    }
 
-    let mut has_rle = false;
-    let mut decode_state: u8 = 3;
-    let mut partial_b8: u8 = 0;
-    let mut last_byte: u8 = 0;
+    pub(crate) fn advance_over(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|b| self.advance(*b));
+    }
 
-    let decode = |val: u8| BYTE_DECODINGS[(val - b'!') as usize] & 0x3F;
+    // moves the position of an error found in input starting here
+    fn offset_error(&self, err: DecodeError) -> DecodeError {
+        match err {
+            DecodeError::IllegalCharacter { byte, line, column } => DecodeError::IllegalCharacter {
+                byte,
+                line: line + self.line - 1,
+                column: if line == 1 {
+                    column + self.column - 1
+                } else {
+                    column
+                },
+            },
+            err => err,
+        }
+    }
+
+    // returns the 6-bit value of the character, or `None` if it is skipped
+    pub(crate) fn push(&mut self, b6: u8) -> Result<Option<u8>, DecodeError> {
+        let (line, column) = (self.line, self.column);
+        self.advance(b6);
 
-    for b6 in encoded_bin_with_newlines.iter() {
-        let b6 = *b6;
-        if b6 == b'\r' || b6 == b'\n' || b6 == EOF {
-            continue;
+        if is_skipped(b6, self.mode) {
+            return Ok(None);
         }
 
-        decode_state = ((decode_state as u8) + 1) % 4;
-        let b6_decoded = decode(b6);
+        decode_char(b6)
+            .map(Some)
+            .ok_or(DecodeError::IllegalCharacter {
+                byte: b6,
+                line,
+                column,
+            })
+    }
+
+    fn advance(&mut self, b: u8) {
+        match b {
+            // \r\n is a single line break
+            b'\n' if self.after_cr => {}
+            b'\r' | b'\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            _ => self.column += 1,
+        }
+        self.after_cr = b == b'\r';
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SixBitDecoder {
+    decode_state: u8,
+    partial_b8: u8,
+}
+
+impl SixBitDecoder {
+    // feeds one decoded 6-bit value, returning a data byte once one is complete
+    pub(crate) fn push(&mut self, b6_decoded: u8) -> Option<u8> {
+        let decode_state = self.decode_state;
+        self.decode_state = (decode_state + 1) % 4;
 
-        let mut data: u8;
         match decode_state {
             0 => {
                 // cannot yet output a data byte
-                partial_b8 = b6_decoded << 2;
-                continue;
+                self.partial_b8 = b6_decoded << 2;
+                None
             }
             1 => {
-                data = partial_b8 | (b6_decoded >> 4);
-                partial_b8 = (b6_decoded & 0x0F) << 4;
+                let data = self.partial_b8 | (b6_decoded >> 4);
+                self.partial_b8 = (b6_decoded & 0x0F) << 4;
+                Some(data)
             }
             2 => {
-                data = partial_b8 | (b6_decoded >> 2);
-                partial_b8 = (b6_decoded & 0x03) << 6;
-            }
-            3 => {
-                data = partial_b8 | b6_decoded;
+                let data = self.partial_b8 | (b6_decoded >> 2);
+                self.partial_b8 = (b6_decoded & 0x03) << 6;
+                Some(data)
             }
+            3 => Some(self.partial_b8 | b6_decoded),
             _ => unreachable!(),
         }
-
-        if !has_rle {
-            if data == RLE_MARKER_BYTE {
-                has_rle = true;
-            } else {
-                last_byte = data;
-                out.push(data);
-            }
-        } else {
-            if data == 0x00 {
-                last_byte = RLE_MARKER_BYTE;
-                out.push(RLE_MARKER_BYTE);
-            } else {
-                loop {
-                    data -= 1;
-                    if data == 0 {
-                        break;
-                    }
-                    out.push(last_byte);
-                }
-            }
-            has_rle = false;
-        }
     }
+}
+
+fn decode(encoded_bin_with_newlines: &[u8], chars: CharDecoder) -> Result<HQX, DecodeError> {
+    let stripped = sixbit::strip(encoded_bin_with_newlines, chars.mode());
+    let compressed = match sixbit::decode(&stripped) {
+        Ok(compressed) => compressed,
+        // go over the input again to find the line and column
+        Err(_) => return Err(illegal_character(encoded_bin_with_newlines, chars)),
+    };
+
+    let mut out = Vec::<u8>::with_capacity(compressed.len());
+    let mut rle = RleDecoder::new(chars.mode());
+    rle.push_all(&compressed, &mut out)?;
+    rle.finish()?;
+
+    Ok(HQX::new(out))
+}
 
-    HQX::new(out)
+fn illegal_character(encoded_bin_with_newlines: &[u8], mut chars: CharDecoder) -> DecodeError {
+    encoded_bin_with_newlines
+        .iter()
+        .find_map(|b6| chars.push(*b6).err())
+        .unwrap_or(DecodeError::BadFormat)
 }
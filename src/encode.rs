@@ -1,7 +1,9 @@
-use lazy_static::lazy_static;
 use std::ffi::CString;
 
-use crate::{error::EncodeError, get_header_len, verify::compute_crc, HQXConfig, HQXRef, HQX};
+use crate::{
+    crc::Crc16, error::EncodeError, get_header_len, rle::compress, sixbit, sixbit::ALPHABET,
+    HQXConfig, HQXRef, HQX,
+};
 
 fn default_name() -> CString {
     CString::new("Untitled.hqx").unwrap()
@@ -24,16 +26,16 @@ pub fn binhex(config: HQXConfig) -> Result<HQX, EncodeError> {
     hqx.push(0); // null-terminate name
 
     // file_type
-    let file_type = config.file_type.copied().unwrap_or_default();
-    file_type.into_iter().for_each(|b| hqx.push(b));
+    let file_type = config.file_type.unwrap_or_default();
+    file_type.to_bytes().into_iter().for_each(|b| hqx.push(b));
 
     // author
-    let author = config.author.copied().unwrap_or_default();
-    author.into_iter().for_each(|b| hqx.push(b));
+    let author = config.author.unwrap_or_default();
+    author.to_bytes().into_iter().for_each(|b| hqx.push(b));
 
     // flags
-    let flags = config.flags.copied().unwrap_or_default();
-    flags.into_iter().for_each(|b| hqx.push(b));
+    let flags = config.flags.unwrap_or_default().sanitized();
+    flags.to_bytes().into_iter().for_each(|b| hqx.push(b));
 
     // data_len
     let data = config.data.unwrap_or_default();
@@ -58,33 +60,18 @@ pub fn binhex(config: HQXConfig) -> Result<HQX, EncodeError> {
         .into_iter()
         .for_each(|b| hqx.push(b));
 
-    // add two zero bytes in place of header crc to compute actual header crc
-    hqx.push(0);
-    hqx.push(0);
-
-    let header_len = get_header_len(name_len.into());
-
     // header crc
-    let hc = compute_crc(&hqx[..header_len]).to_be_bytes();
-    hqx.pop();
-    hqx.pop();
+    let header_len = get_header_len(name_len.into());
+    let hc = Crc16::checksum(&hqx[..header_len - 2]).to_be_bytes();
     hqx.push(hc[0]);
     hqx.push(hc[1]);
 
     // data
     if data_len > 0 {
-        let data_start = hqx.len();
-
         data.iter().for_each(|b| hqx.push(*b));
 
-        // crc placeholder
-        hqx.push(0);
-        hqx.push(0);
-
         // data crc
-        let dc = compute_crc(&hqx[data_start..data_start + data_len as usize + 2]);
-        hqx.pop();
-        hqx.pop();
+        let dc = Crc16::checksum(data);
         dc.to_be_bytes().into_iter().for_each(|b| hqx.push(b));
     } else {
         hqx.push(0);
@@ -93,18 +80,10 @@ pub fn binhex(config: HQXConfig) -> Result<HQX, EncodeError> {
 
     // resource
     if resource_len > 0 {
-        let resource_start = hqx.len();
-
         resource.iter().for_each(|b| hqx.push(*b));
 
-        // crc placeholder
-        hqx.push(0);
-        hqx.push(0);
-
         // resource crc
-        let rc = compute_crc(&hqx[resource_start..resource_start + resource_len as usize + 2]);
-        hqx.pop();
-        hqx.pop();
+        let rc = Crc16::checksum(resource);
         rc.to_be_bytes().into_iter().for_each(|b| hqx.push(b));
     } else {
         hqx.push(0);
@@ -114,103 +93,190 @@ pub fn binhex(config: HQXConfig) -> Result<HQX, EncodeError> {
     Ok(HQX { vec: hqx })
 }
 
-fn len_to_encoded_len(len: usize) -> usize {
-    (len / 3) * 4
+pub(crate) const BINHEX_FILE_MARKER: &[u8] = b"(This file must be converted with BinHex 4.0)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    // classic Mac OS, as written by the original BinHex 4.0
+    Cr,
+    Lf,
+    #[default]
+    CrLf,
 }
 
-const BINHEX_FILE_MARKER: &[u8] = "(This file must be converted with BinHex 4.0)\n\n".as_bytes();
+impl LineEnding {
+    pub const fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Cr => b"\r",
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
 
-impl<'a> HQXRef<'a> {
-    pub fn encode(&self) -> Vec<u8> {
-        let data = self
-            .data_fork
-            .as_ref()
-            .map(|fork| fork.data)
-            .unwrap_or_default();
-        let resource = self
-            .data_fork
-            .as_ref()
-            .map(|fork| fork.data)
-            .unwrap_or_default();
-
-        let encoded_len = len_to_encoded_len(
-            self.header_len as usize // header
-                + data.len() // data
-                + 2 // data crc
-                + resource.len() // resource
-                + 2, // resource crc
-        );
-
-        let bytes = &self.hqx.vec[..];
-
-        let newline_count = (bytes.len() / 64) + 1;
-
-        let mut encoded = Vec::with_capacity(
-            BINHEX_FILE_MARKER.len() // file marker
-            + 1 // colon
-            + encoded_len // data len
-            + newline_count * 2 // number of newlines (*2 for \r\n)
-            + 1 // colon
-            + 2, // \r\n
-        );
-
-        BINHEX_FILE_MARKER.iter().for_each(|b| encoded.push(*b));
-        encoded.push(b':');
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeOptions {
+    // encoded characters per line, or 0 to never break lines
+    pub line_length: usize,
+    pub line_ending: LineEnding,
+    // whether to write the `(This file must be converted with BinHex 4.0)` line
+    pub marker: bool,
+    // written as is before the marker line, such as a comment about the file
+    pub preamble: Vec<u8>,
+    // BinHex 4.0 counted the opening colon as part of the first line, which
+    // leaves room for one character fewer on it
+    pub short_first_line: bool,
+}
 
-        let mut encode_state: u8 = 0;
-        let mut save_bits: u32 = 0;
-        let mut next_byte: u8;
-
-        let mut bytes_in_line = 0;
-        let encode_byte = |b: u8| BYTE_ENCODINGS[b as usize];
-        let mut push_byte = |b: u8| {
-            encoded.push(encode_byte(b));
-            bytes_in_line += 1;
-            if bytes_in_line == 64 {
-                encoded.push(b'\r');
-                encoded.push(b'\n');
-                bytes_in_line = 0;
-            }
-        };
+impl Default for EncodeOptions {
+    fn default() -> EncodeOptions {
+        EncodeOptions {
+            line_length: 64,
+            line_ending: LineEnding::default(),
+            marker: true,
+            preamble: Vec::new(),
+            short_first_line: false,
+        }
+    }
+}
 
-        for byte in bytes.iter().copied() {
-            match encode_state {
-                0 => {
-                    next_byte = (byte >> 2) & 0x3F;
-                    push_byte(next_byte);
-                    save_bits = (byte & 0x03) as u32;
-                }
-                1 => {
-                    next_byte = (((save_bits << 4) & 0x30) as u8) | (byte >> 4);
-                    push_byte(next_byte);
-                    save_bits = (byte & 0x0F) as u32;
-                }
-                2 => {
-                    next_byte = (((save_bits << 2) & 0x3C) as u8) | (byte >> 6);
-                    push_byte(next_byte);
-                    next_byte = byte & 0x3F;
-                    push_byte(next_byte);
-                }
-                _ => unreachable!(),
-            }
-            encode_state = (encode_state + 1) % 3;
+impl EncodeOptions {
+    // the preamble, marker line and opening colon
+    pub(crate) fn write_prologue(&self, encoded: &mut Vec<u8>) {
+        encoded.extend_from_slice(&self.preamble);
+        if self.marker {
+            encoded.extend_from_slice(BINHEX_FILE_MARKER);
+            encoded.extend_from_slice(self.line_ending.as_bytes());
+            encoded.extend_from_slice(self.line_ending.as_bytes());
         }
+        encoded.push(b':');
+    }
 
+    pub(crate) fn write_epilogue(&self, encoded: &mut Vec<u8>) {
         encoded.push(b':');
-        encoded.push(b'\r');
-        encoded.push(b'\n');
+        encoded.extend_from_slice(self.line_ending.as_bytes());
+    }
+
+    // breaks encoded characters into lines the way `SixBitEncoder` does,
+    // ending every full line, the last included
+    pub(crate) fn write_lines(&self, chars: &[u8], encoded: &mut Vec<u8>) {
+        if self.line_length == 0 {
+            encoded.extend_from_slice(chars);
+            return;
+        }
+
+        let mut line_length = self.first_line_length();
+        let mut chars = chars;
+        while chars.len() >= line_length {
+            let (line, rest) = chars.split_at(line_length);
+            encoded.extend_from_slice(line);
+            encoded.extend_from_slice(self.line_ending.as_bytes());
+            chars = rest;
+            line_length = self.line_length;
+        }
+        encoded.extend_from_slice(chars);
+    }
+
+    // a one-character line has no room to share with the colon, so it stays
+    // one character long
+    fn first_line_length(&self) -> usize {
+        self.line_length
+            .saturating_sub(self.short_first_line as usize)
+            .max(1)
+    }
+
+    fn encoded_len(&self, compressed_len: usize) -> usize {
+        let chars = compressed_len.div_ceil(3) * 4;
+        let lines = match self.line_length {
+            0 => 1,
+            line_length => chars / line_length + 1,
+        };
+
+        self.preamble.len()
+            + BINHEX_FILE_MARKER.len()
+            + 2 // colons
+            + chars
+            + (lines + 2) * self.line_ending.as_bytes().len()
+    }
+}
+
+impl<'a> HQXRef<'a> {
+    pub fn encode(&self) -> Vec<u8> {
+        self.encode_with(&EncodeOptions::default())
+    }
+
+    pub fn encode_with(&self, options: &EncodeOptions) -> Vec<u8> {
+        let compressed = compress(&self.hqx.vec);
+
+        let mut encoded = Vec::with_capacity(options.encoded_len(compressed.len()));
+        options.write_prologue(&mut encoded);
+
+        options.write_lines(&sixbit::encode(&compressed), &mut encoded);
+        options.write_epilogue(&mut encoded);
 
         encoded
     }
 }
 
-lazy_static! {
-    static ref BYTE_ENCODINGS: [u8; 64] = create_byte_encodings();
+#[derive(Debug)]
+pub(crate) struct SixBitEncoder {
+    encode_state: u8,
+    save_bits: u8,
+    bytes_in_line: usize,
+    line_length: usize,
+    line_ending: &'static [u8],
 }
 
-fn create_byte_encodings() -> [u8; 64] {
-    "!\"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr"
-        .as_bytes()
-        .try_into()
-        .unwrap()
+impl SixBitEncoder {
+    pub(crate) fn new(options: &EncodeOptions) -> SixBitEncoder {
+        SixBitEncoder {
+            encode_state: 0,
+            save_bits: 0,
+            // the opening colon may already be on the first line
+            bytes_in_line: options
+                .line_length
+                .saturating_sub(options.first_line_length()),
+            line_length: options.line_length,
+            line_ending: options.line_ending.as_bytes(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, byte: u8, encoded: &mut Vec<u8>) {
+        match self.encode_state {
+            0 => {
+                self.push_char((byte >> 2) & 0x3F, encoded);
+                self.save_bits = byte & 0x03;
+            }
+            1 => {
+                self.push_char(((self.save_bits << 4) & 0x30) | (byte >> 4), encoded);
+                self.save_bits = byte & 0x0F;
+            }
+            2 => {
+                self.push_char(((self.save_bits << 2) & 0x3C) | (byte >> 6), encoded);
+                self.push_char(byte & 0x3F, encoded);
+            }
+            _ => unreachable!(),
+        }
+        self.encode_state = (self.encode_state + 1) % 3;
+    }
+
+    // flushes the bits left over from a trailing partial group
+    pub(crate) fn finish(&mut self, encoded: &mut Vec<u8>) {
+        match self.encode_state {
+            0 => {}
+            1 => self.push_char((self.save_bits << 4) & 0x30, encoded),
+            2 => self.push_char((self.save_bits << 2) & 0x3C, encoded),
+            _ => unreachable!(),
+        }
+        self.encode_state = 0;
+    }
+
+    fn push_char(&mut self, b6: u8, encoded: &mut Vec<u8>) {
+        encoded.push(ALPHABET[b6 as usize]);
+        self.bytes_in_line += 1;
+        if self.bytes_in_line == self.line_length {
+            encoded.extend_from_slice(self.line_ending);
+            self.bytes_in_line = 0;
+        }
+    }
 }
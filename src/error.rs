@@ -1,9 +1,46 @@
+use std::fmt;
+
+use crate::ForkKind;
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum DecodeError {
     BadFormat,
     BadRunLengthEncoding,
+    IllegalCharacter {
+        byte: u8,
+        line: usize,
+        column: usize,
+    },
     CRCVerificationError(CRCVerificationError),
+    Parse(ParseError),
+    Io(std::io::Error),
+    NestedTooDeeply,
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    // the buffer ends before the end of the header
+    TruncatedHeader {
+        len: usize,
+        expected: usize,
+    },
+    // the name at `offset` is not followed by a null byte, or contains one
+    BadName {
+        offset: usize,
+    },
+    // the fork starting at `offset` runs past the end of the buffer
+    ForkOutOfBounds {
+        fork: ForkKind,
+        offset: usize,
+        len: u32,
+        available: usize,
+    },
+    // the two crc bytes expected at `offset` are missing
+    MissingCRC {
+        fork: ForkKind,
+        offset: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -17,6 +54,303 @@ pub enum CRCVerificationError {
 #[non_exhaustive]
 pub enum EncodeError {
     FileNameTooLong,
+    BadFileName,
+    NameNotMacRoman,
     DataTooLarge,
     ResourceTooLarge,
+    ForkLengthMismatch,
+    Io(std::io::Error),
+}
+
+// why `HqxBuilder::build` refused its parts; offsets and lengths in names
+// count Mac OS Roman characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildError {
+    NameEmpty,
+    NameTooLong { len: usize },
+    NameTooLongForHfs { len: usize },
+    NameContainsNul { offset: usize },
+    NameContainsColon { offset: usize },
+    NameNotMacRoman { character: char },
+    DataForkTooLarge { len: usize },
+    ResourceForkTooLarge { len: usize },
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ResourceError {
+    TruncatedHeader,
+    // a structure at `offset` runs past the end of the fork
+    OutOfBounds { offset: usize },
+    NameTooLong,
+    DataTooLarge,
+    MapTooLarge,
+    TooManyResources,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MacBinaryError {
+    NotMacBinary,
+    Truncated,
+    BadName,
+    ForkTooLarge,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AppleFileError {
+    BadMagic,
+    UnsupportedVersion(u32),
+    Truncated,
+    EntryOutOfBounds { id: u32 },
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FourCharCodeError {
+    BadLength,
+    NotMacRoman,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadFormat => write!(f, "input is not a valid BinHex file"),
+            DecodeError::BadRunLengthEncoding => write!(f, "invalid run-length encoding"),
+            DecodeError::IllegalCharacter { byte, line, column } => write!(
+                f,
+                "illegal character 0x{:02X} at line {}, column {}",
+                byte, line, column
+            ),
+            DecodeError::CRCVerificationError(err) => write!(f, "{}", err),
+            DecodeError::Parse(err) => write!(f, "{}", err),
+            DecodeError::Io(err) => write!(f, "{}", err),
+            DecodeError::NestedTooDeeply => write!(f, "MIME entities are nested too deeply"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::TruncatedHeader { len, expected } => write!(
+                f,
+                "truncated header: expected {} bytes, found {}",
+                expected, len
+            ),
+            ParseError::BadName { offset } => write!(f, "bad file name at byte {}", offset),
+            ParseError::ForkOutOfBounds {
+                fork,
+                offset,
+                len,
+                available,
+            } => write!(
+                f,
+                "{} fork of {} bytes at byte {} runs past the end ({} bytes available)",
+                fork, len, offset, available
+            ),
+            ParseError::MissingCRC { fork, offset } => {
+                write!(f, "missing {} fork CRC at byte {}", fork, offset)
+            }
+        }
+    }
+}
+
+impl fmt::Display for CRCVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CRCVerificationError::Header => write!(f, "header CRC mismatch"),
+            CRCVerificationError::Data => write!(f, "data fork CRC mismatch"),
+            CRCVerificationError::Resource => write!(f, "resource fork CRC mismatch"),
+        }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::FileNameTooLong => write!(f, "file name is longer than 255 bytes"),
+            EncodeError::BadFileName => write!(f, "file name contains a null byte"),
+            EncodeError::NameNotMacRoman => {
+                write!(f, "file name has characters outside of Mac OS Roman")
+            }
+            EncodeError::DataTooLarge => write!(f, "data fork is larger than 4 GiB"),
+            EncodeError::ResourceTooLarge => write!(f, "resource fork is larger than 4 GiB"),
+            EncodeError::ForkLengthMismatch => {
+                write!(f, "fork data does not match the lengths in the header")
+            }
+            EncodeError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::NameEmpty => write!(f, "file name is empty"),
+            BuildError::NameTooLong { len } => {
+                write!(f, "file name is {} bytes long, more than 255", len)
+            }
+            BuildError::NameTooLongForHfs { len } => write!(
+                f,
+                "file name is {} characters long, more than the 63 HFS allows",
+                len
+            ),
+            BuildError::NameContainsNul { offset } => {
+                write!(f, "file name has a null byte at character {}", offset)
+            }
+            BuildError::NameContainsColon { offset } => {
+                write!(f, "file name has a ':' at character {}", offset)
+            }
+            BuildError::NameNotMacRoman { character } => {
+                write!(
+                    f,
+                    "file name character {:?} is not in Mac OS Roman",
+                    character
+                )
+            }
+            BuildError::DataForkTooLarge { len } => {
+                write!(f, "data fork of {} bytes is larger than 4 GiB", len)
+            }
+            BuildError::ResourceForkTooLarge { len } => {
+                write!(f, "resource fork of {} bytes is larger than 4 GiB", len)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceError::TruncatedHeader => write!(f, "truncated resource fork header"),
+            ResourceError::OutOfBounds { offset } => {
+                write!(
+                    f,
+                    "resource fork structure at byte {} is out of bounds",
+                    offset
+                )
+            }
+            ResourceError::NameTooLong => write!(f, "resource name is longer than 255 bytes"),
+            ResourceError::DataTooLarge => write!(f, "resource data is larger than 16 MiB"),
+            ResourceError::MapTooLarge => write!(f, "resource map is larger than 64 KiB"),
+            ResourceError::TooManyResources => write!(f, "too many resources for a resource map"),
+        }
+    }
+}
+
+impl fmt::Display for MacBinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacBinaryError::NotMacBinary => write!(f, "input is not a MacBinary file"),
+            MacBinaryError::Truncated => write!(f, "MacBinary file is truncated"),
+            MacBinaryError::BadName => write!(f, "file name must be 1 to 63 bytes long"),
+            MacBinaryError::ForkTooLarge => write!(f, "fork is too large for MacBinary"),
+        }
+    }
+}
+
+impl fmt::Display for AppleFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppleFileError::BadMagic => {
+                write!(f, "input is not an AppleSingle or AppleDouble file")
+            }
+            AppleFileError::UnsupportedVersion(version) => {
+                write!(f, "unsupported AppleSingle version 0x{:08X}", version)
+            }
+            AppleFileError::Truncated => write!(f, "AppleSingle header is truncated"),
+            AppleFileError::EntryOutOfBounds { id } => {
+                write!(f, "entry {} runs past the end of the file", id)
+            }
+            AppleFileError::TooLarge => write!(f, "entries are too large for AppleSingle"),
+        }
+    }
+}
+
+impl fmt::Display for FourCharCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FourCharCodeError::BadLength => write!(f, "code must be exactly four characters"),
+            FourCharCodeError::NotMacRoman => {
+                write!(f, "code has characters outside of Mac OS Roman")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Parse(err) => Some(err),
+            DecodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::error::Error for BuildError {}
+
+impl std::error::Error for ResourceError {}
+
+impl std::error::Error for MacBinaryError {}
+
+impl std::error::Error for AppleFileError {}
+
+impl std::error::Error for FourCharCodeError {}
+
+impl std::error::Error for CRCVerificationError {}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(err: std::io::Error) -> DecodeError {
+        DecodeError::Io(err)
+    }
+}
+
+impl From<ParseError> for DecodeError {
+    fn from(err: ParseError) -> DecodeError {
+        DecodeError::Parse(err)
+    }
+}
+
+impl From<DecodeError> for std::io::Error {
+    fn from(err: DecodeError) -> std::io::Error {
+        match err {
+            DecodeError::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+impl From<std::io::Error> for EncodeError {
+    fn from(err: std::io::Error) -> EncodeError {
+        EncodeError::Io(err)
+    }
+}
+
+impl From<BuildError> for std::io::Error {
+    fn from(err: BuildError) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, err)
+    }
+}
+
+impl From<EncodeError> for std::io::Error {
+    fn from(err: EncodeError) -> std::io::Error {
+        match err {
+            EncodeError::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::InvalidInput, err),
+        }
+    }
 }
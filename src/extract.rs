@@ -0,0 +1,218 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    applefile::{apple_double_path, AppleFileKind, AppleFileVersion},
+    filename::host_file_name,
+    macbinary::MacBinary,
+    sidecar::{netatalk_path, FINDER_INFO_ATTRIBUTES, RESOURCE_FORK_ATTRIBUTES},
+    HQXRef,
+};
+
+// seconds from 1904-01-01, where classic Mac OS dates start, to 1970-01-01
+const MAC_EPOCH_OFFSET: u64 = 2_082_844_800;
+
+// where the resource fork and Finder info go when extracting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataFormat {
+    // a `._NAME` AppleDouble file next to the data fork, as macOS writes
+    #[default]
+    AppleDouble,
+    // `.AppleDouble/NAME`, as Netatalk 2 keeps them
+    Netatalk,
+    // `com.apple.ResourceFork` and `com.apple.FinderInfo` attributes of the
+    // data fork, in the `user` namespace outside of macOS
+    ExtendedAttributes,
+    // the resource fork alone in `NAME.rsrc`; Finder info is dropped
+    ResourceFile,
+    // one MacBinary III file, `NAME.bin`, instead of the data fork
+    MacBinary,
+    // the data fork only
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExtractOptions {
+    pub metadata: MetadataFormat,
+    // BinHex headers carry no dates, so this is usually the modification
+    // time of the archive itself; `None` leaves the files' times alone
+    pub modified: Option<SystemTime>,
+    pub overwrite: bool,
+}
+
+impl ExtractOptions {
+    // dates the extracted files like the archive at `path`
+    pub fn modified_like<P: AsRef<Path>>(mut self, path: P) -> io::Result<ExtractOptions> {
+        self.modified = Some(fs::metadata(path)?.modified()?);
+        Ok(self)
+    }
+}
+
+impl<'a> HQXRef<'a> {
+    // writes every fork and the Finder info to `path`, or to a file named
+    // after this one if `path` is a directory, returning the files written.
+    // fails before writing anything if a file exists and `overwrite` is off
+    pub fn extract<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &ExtractOptions,
+    ) -> io::Result<Vec<PathBuf>> {
+        let path = {
+            let mut path = path.as_ref().to_path_buf();
+            if path.is_dir() {
+                path.push(host_file_name(self.name));
+            }
+            path
+        };
+
+        let files = self.extracted_files(&path, options)?;
+        if !options.overwrite {
+            if let Some((existing, _)) = files.iter().find(|(path, _)| path.exists()) {
+                return Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("{} already exists", existing.display()),
+                ));
+            }
+        }
+
+        let mut written = Vec::with_capacity(files.len());
+        for (path, bytes) in files {
+            if let Some(parent) = path.parent().filter(|parent| !parent.exists()) {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = create(&path, options.overwrite)?;
+            file.write_all(&bytes)?;
+            if let Some(modified) = options.modified {
+                file.set_modified(modified)?;
+            }
+            written.push(path);
+        }
+
+        if options.metadata == MetadataFormat::ExtendedAttributes {
+            self.write_attributes(&written[0])?;
+        }
+
+        Ok(written)
+    }
+
+    fn extracted_files(
+        &self,
+        path: &Path,
+        options: &ExtractOptions,
+    ) -> io::Result<Vec<(PathBuf, Vec<u8>)>> {
+        let data = self
+            .data_fork
+            .as_ref()
+            .map(|fork| fork.data.to_vec())
+            .unwrap_or_default();
+        let resource = self.resource_fork.as_ref().map(|fork| fork.data.to_vec());
+
+        let apple_double = || {
+            self.to_apple_file(AppleFileKind::Double, AppleFileVersion::V2)
+                .to_bytes()
+                .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+        };
+
+        let mut files = Vec::with_capacity(2);
+        match options.metadata {
+            MetadataFormat::AppleDouble | MetadataFormat::Netatalk => {
+                files.push((path.to_path_buf(), data));
+                if self.has_metadata() {
+                    let sidecar = match options.metadata {
+                        MetadataFormat::AppleDouble => apple_double_path(path)?,
+                        _ => netatalk_path(path)?,
+                    };
+                    files.push((sidecar, apple_double()?));
+                }
+            }
+            MetadataFormat::ResourceFile => {
+                files.push((path.to_path_buf(), data));
+                if let Some(resource) = resource {
+                    files.push((with_suffix(path, ".rsrc"), resource));
+                }
+            }
+            MetadataFormat::MacBinary => {
+                let mut macbinary = MacBinary::from(self);
+                if let Some(modified) = options.modified {
+                    macbinary.created = mac_time(modified);
+                    macbinary.modified = mac_time(modified);
+                }
+                let bytes = macbinary
+                    .to_bytes()
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+                files.push((with_suffix(path, ".bin"), bytes));
+            }
+            MetadataFormat::ExtendedAttributes | MetadataFormat::None => {
+                files.push((path.to_path_buf(), data));
+            }
+        }
+
+        Ok(files)
+    }
+
+    // whether there is anything for a sidecar to hold
+    fn has_metadata(&self) -> bool {
+        self.resource_fork.is_some()
+            || !self.file_type.is_zero()
+            || !self.author.is_zero()
+            || self.flags.to_bytes() != [0, 0]
+    }
+
+    #[cfg(unix)]
+    fn write_attributes(&self, path: &Path) -> io::Result<()> {
+        // macOS names them without a namespace, Linux needs `user`
+        let index = if cfg!(target_os = "macos") { 0 } else { 1 };
+
+        let mut finder_info = [0; 32];
+        finder_info[0..4].copy_from_slice(self.file_type.as_bytes());
+        finder_info[4..8].copy_from_slice(self.author.as_bytes());
+        finder_info[8..10].copy_from_slice(&self.flags.to_bytes());
+        xattr::set(path, FINDER_INFO_ATTRIBUTES[index], &finder_info)?;
+
+        match self.resource_fork.as_ref() {
+            Some(fork) => xattr::set(path, RESOURCE_FORK_ATTRIBUTES[index], fork.data),
+            // an overwritten file may still have the attribute; there is
+            // nothing to do if it does not
+            None => {
+                let _ = xattr::remove(path, RESOURCE_FORK_ATTRIBUTES[index]);
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn write_attributes(&self, _: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "extended attributes are only supported on unix",
+        ))
+    }
+}
+
+fn create(path: &Path, overwrite: bool) -> io::Result<File> {
+    if overwrite {
+        File::create(path)
+    } else {
+        // also catches a file created since the check in `extract`
+        OpenOptions::new().write(true).create_new(true).open(path)
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn mac_time(time: SystemTime) -> u32 {
+    let unix = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    (unix + MAC_EPOCH_OFFSET).try_into().unwrap_or(u32::MAX)
+}
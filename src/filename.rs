@@ -0,0 +1,124 @@
+use std::{
+    collections::HashSet,
+    ffi::CStr,
+    path::{Path, PathBuf},
+};
+
+use crate::macroman;
+
+// longest file name most host file systems accept, in bytes
+const MAX_HOST_NAME_LEN: usize = 255;
+
+// maps classic Mac OS names to names that are safe to create on the host:
+// no path separators, no control characters, never `.` or `..`, and never a
+// name this mapper has already handed out
+pub struct FileNameMapper {
+    substitute: Box<dyn Fn(char) -> char>,
+    taken: HashSet<String>,
+}
+
+impl FileNameMapper {
+    // replaces `/` (legal on classic Mac OS) and `:` with `_`
+    pub fn new() -> FileNameMapper {
+        FileNameMapper::with_substitution(|_| '_')
+    }
+
+    // `substitute` is called for every `/` and `:` in a name; returning `/`
+    // (or another unsafe character) falls back to `_`
+    pub fn with_substitution<F: Fn(char) -> char + 'static>(substitute: F) -> FileNameMapper {
+        FileNameMapper {
+            substitute: Box::new(substitute),
+            taken: HashSet::new(),
+        }
+    }
+
+    pub fn sanitize(&self, name: &CStr) -> String {
+        let name = macroman::decode(name.to_bytes());
+
+        let mut sanitized = String::with_capacity(name.len());
+        for c in name.chars() {
+            let c = match c {
+                '/' | ':' => (self.substitute)(c),
+                c => c,
+            };
+            let c = if is_safe(c) { c } else { '_' };
+            if sanitized.len() + c.len_utf8() > MAX_HOST_NAME_LEN {
+                break;
+            }
+            sanitized.push(c);
+        }
+
+        match sanitized.as_str() {
+            "" | "." | ".." => "_".repeat(sanitized.len().max(1)),
+            _ => sanitized,
+        }
+    }
+
+    // sanitizes `name`, adding a numeric suffix if the result was already
+    // returned by this mapper (compared case-insensitively)
+    pub fn map(&mut self, name: &CStr) -> String {
+        self.map_with(name, |_| false)
+    }
+
+    // like `map`, but also avoids names that already exist in `dir`
+    pub fn map_in_dir<P: AsRef<Path>>(&mut self, dir: P, name: &CStr) -> PathBuf {
+        let dir = dir.as_ref();
+        let name = self.map_with(name, |candidate| dir.join(candidate).exists());
+        dir.join(name)
+    }
+
+    fn map_with<F: Fn(&str) -> bool>(&mut self, name: &CStr, exists: F) -> String {
+        let name = self.sanitize(name);
+
+        let (stem, extension) = match name.rfind('.') {
+            Some(i) if i > 0 => name.split_at(i),
+            _ => (name.as_str(), ""),
+        };
+
+        let mut candidate = name.clone();
+        let mut n = 2;
+        while self.taken.contains(&candidate.to_lowercase()) || exists(&candidate) {
+            let number = format!(" {}", n);
+            // an extension too long to fit beside the number is cut short
+            // too, keeping at least the first character of the stem
+            let first = stem.chars().next().map_or(0, char::len_utf8);
+            let extension = truncate(
+                extension,
+                MAX_HOST_NAME_LEN.saturating_sub(number.len() + first),
+            );
+            let stem = truncate(
+                stem,
+                MAX_HOST_NAME_LEN.saturating_sub(number.len() + extension.len()),
+            );
+            candidate = format!("{}{}{}", stem, number, extension);
+            n += 1;
+        }
+
+        self.taken.insert(candidate.to_lowercase());
+        candidate
+    }
+}
+
+impl Default for FileNameMapper {
+    fn default() -> FileNameMapper {
+        FileNameMapper::new()
+    }
+}
+
+// sanitizes a single name with the default substitution
+pub fn host_file_name(name: &CStr) -> String {
+    FileNameMapper::new().sanitize(name)
+}
+
+// the longest prefix of `s` that fits in `max` bytes
+fn truncate(s: &str, max: usize) -> &str {
+    let mut len = s.len().min(max);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    &s[..len]
+}
+
+fn is_safe(c: char) -> bool {
+    !c.is_control() && c != '/' && c != '\\'
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+// Finder flags as stored big-endian in the header of an HQX file
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FinderFlags(u16);
+
+macro_rules! flag_accessors {
+    ($($flag:ident, $is:ident, $set:ident;)*) => {
+        $(
+            pub fn $is(&self) -> bool {
+                self.0 & FinderFlags::$flag != 0
+            }
+
+            pub fn $set(&mut self, value: bool) {
+                if value {
+                    self.0 |= FinderFlags::$flag;
+                } else {
+                    self.0 &= !FinderFlags::$flag;
+                }
+            }
+        )*
+    };
+}
+
+impl FinderFlags {
+    pub const IS_ON_DESK: u16 = 0x0001;
+    pub const COLOR: u16 = 0x000E;
+    pub const REQUIRE_SWITCH_LAUNCH: u16 = 0x0020;
+    pub const IS_SHARED: u16 = 0x0040;
+    pub const HAS_NO_INITS: u16 = 0x0080;
+    pub const HAS_BEEN_INITED: u16 = 0x0100;
+    // "changed" in the System 6 Finder, reserved since
+    pub const CHANGED: u16 = 0x0200;
+    pub const HAS_CUSTOM_ICON: u16 = 0x0400;
+    pub const IS_STATIONERY: u16 = 0x0800;
+    pub const NAME_LOCKED: u16 = 0x1000;
+    pub const HAS_BUNDLE: u16 = 0x2000;
+    pub const IS_INVISIBLE: u16 = 0x4000;
+    pub const IS_ALIAS: u16 = 0x8000;
+
+    // describe the state of the file on the encoding machine rather than the
+    // file itself, so BinHex 4.0 clears them when encoding
+    pub const BINHEX_CLEARED: u16 =
+        FinderFlags::IS_ON_DESK | FinderFlags::HAS_BEEN_INITED | FinderFlags::CHANGED;
+
+    pub const fn new(bits: u16) -> FinderFlags {
+        FinderFlags(bits)
+    }
+
+    pub const fn from_bytes(bytes: [u8; 2]) -> FinderFlags {
+        FinderFlags(u16::from_be_bytes(bytes))
+    }
+
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub const fn to_bytes(&self) -> [u8; 2] {
+        self.0.to_be_bytes()
+    }
+
+    pub const fn sanitized(&self) -> FinderFlags {
+        FinderFlags(self.0 & !FinderFlags::BINHEX_CLEARED)
+    }
+
+    // the color label, from 0 (none) to 7
+    pub fn color(&self) -> u8 {
+        ((self.0 & FinderFlags::COLOR) >> 1) as u8
+    }
+
+    pub fn set_color(&mut self, color: u8) {
+        self.0 = (self.0 & !FinderFlags::COLOR) | (((color as u16) << 1) & FinderFlags::COLOR);
+    }
+
+    flag_accessors! {
+        IS_ON_DESK, is_on_desk, set_on_desk;
+        REQUIRE_SWITCH_LAUNCH, requires_switch_launch, set_requires_switch_launch;
+        IS_SHARED, is_shared, set_shared;
+        HAS_NO_INITS, has_no_inits, set_has_no_inits;
+        HAS_BEEN_INITED, has_been_inited, set_has_been_inited;
+        CHANGED, is_changed, set_changed;
+        HAS_CUSTOM_ICON, has_custom_icon, set_has_custom_icon;
+        IS_STATIONERY, is_stationery, set_stationery;
+        NAME_LOCKED, is_name_locked, set_name_locked;
+        HAS_BUNDLE, has_bundle, set_has_bundle;
+        IS_INVISIBLE, is_invisible, set_invisible;
+        IS_ALIAS, is_alias, set_alias;
+    }
+}
+
+impl From<u16> for FinderFlags {
+    fn from(bits: u16) -> FinderFlags {
+        FinderFlags(bits)
+    }
+}
+
+impl From<[u8; 2]> for FinderFlags {
+    fn from(bytes: [u8; 2]) -> FinderFlags {
+        FinderFlags::from_bytes(bytes)
+    }
+}
+
+impl fmt::Debug for FinderFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FinderFlags({:#06X})", self.0)
+    }
+}
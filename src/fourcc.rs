@@ -0,0 +1,145 @@
+use std::{fmt, str::FromStr};
+
+use crate::{error::FourCharCodeError, macroman};
+
+// a classic Mac OS type or creator code, such as `TEXT` or `ttxt`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct FourCharCode(pub [u8; 4]);
+
+impl FourCharCode {
+    pub const fn new(bytes: &[u8; 4]) -> FourCharCode {
+        FourCharCode(*bytes)
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+
+    pub const fn to_bytes(&self) -> [u8; 4] {
+        self.0
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        u32::from_be_bytes(self.0) == 0
+    }
+}
+
+impl From<[u8; 4]> for FourCharCode {
+    fn from(bytes: [u8; 4]) -> FourCharCode {
+        FourCharCode(bytes)
+    }
+}
+
+impl From<FourCharCode> for [u8; 4] {
+    fn from(code: FourCharCode) -> [u8; 4] {
+        code.0
+    }
+}
+
+impl PartialEq<[u8; 4]> for FourCharCode {
+    fn eq(&self, other: &[u8; 4]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for FourCharCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", macroman::decode(&self.0))
+    }
+}
+
+impl fmt::Debug for FourCharCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FourCharCode({:?})", macroman::decode(&self.0))
+    }
+}
+
+impl FromStr for FourCharCode {
+    type Err = FourCharCodeError;
+
+    fn from_str(s: &str) -> Result<FourCharCode, FourCharCodeError> {
+        let bytes = macroman::encode(s).ok_or(FourCharCodeError::NotMacRoman)?;
+        let bytes = <[u8; 4]>::try_from(bytes).map_err(|_| FourCharCodeError::BadLength)?;
+        Ok(FourCharCode(bytes))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileKind {
+    pub file_type: FourCharCode,
+    // `None` matches any creator
+    pub creator: Option<FourCharCode>,
+    pub mime_type: &'static str,
+    pub extension: &'static str,
+}
+
+macro_rules! file_kinds {
+    ($(($file_type:literal, $creator:expr, $mime_type:literal, $extension:literal),)*) => {
+        &[$(FileKind {
+            file_type: FourCharCode::new($file_type),
+            creator: $creator,
+            mime_type: $mime_type,
+            extension: $extension,
+        },)*]
+    };
+}
+
+const fn creator(bytes: &[u8; 4]) -> Option<FourCharCode> {
+    Some(FourCharCode::new(bytes))
+}
+
+// entries with a specific creator come before the catch-all for their type
+pub static FILE_KINDS: &[FileKind] = file_kinds![
+    (b"WORD", creator(b"MSWD"), "application/msword", "doc"),
+    (b"W6BN", creator(b"MSWD"), "application/msword", "doc"),
+    (b"W8BN", creator(b"MSWD"), "application/msword", "doc"),
+    (b"WORD", creator(b"MACA"), "application/x-macwrite", "mcw"),
+    (b"XLS ", creator(b"XCEL"), "application/vnd.ms-excel", "xls"),
+    (b"XLS8", creator(b"XCEL"), "application/vnd.ms-excel", "xls"),
+    (b"TEXT", None, "text/plain", "txt"),
+    (b"ttro", None, "text/plain", "txt"),
+    (b"RTF ", None, "application/rtf", "rtf"),
+    (b"HTML", None, "text/html", "html"),
+    (b"PDF ", None, "application/pdf", "pdf"),
+    (b"EPSF", None, "application/postscript", "eps"),
+    (b"GIFf", None, "image/gif", "gif"),
+    (b"JPEG", None, "image/jpeg", "jpg"),
+    (b"PNGf", None, "image/png", "png"),
+    (b"TIFF", None, "image/tiff", "tif"),
+    (b"BMP ", None, "image/bmp", "bmp"),
+    (b"BMPf", None, "image/bmp", "bmp"),
+    (b"PICT", None, "image/x-pict", "pict"),
+    (b"8BPS", None, "image/vnd.adobe.photoshop", "psd"),
+    (b"AIFF", None, "audio/aiff", "aiff"),
+    (b"AIFC", None, "audio/aiff", "aifc"),
+    (b"WAVE", None, "audio/wav", "wav"),
+    (b"Midi", None, "audio/midi", "mid"),
+    (b"MooV", None, "video/quicktime", "mov"),
+    (b"MPEG", None, "video/mpeg", "mpg"),
+    (b"SIT!", None, "application/x-stuffit", "sit"),
+    (b"SITD", None, "application/x-stuffit", "sit"),
+    (b"SIT5", None, "application/x-stuffit", "sit"),
+    (b"PACT", None, "application/x-compactpro", "cpt"),
+    (b"PIT ", None, "application/x-pit", "pit"),
+    (b"ZIP ", None, "application/zip", "zip"),
+    (b"Gzip", None, "application/gzip", "gz"),
+    (b"TARF", None, "application/x-tar", "tar"),
+    (b"BINA", None, "application/octet-stream", "bin"),
+];
+
+pub fn lookup(file_type: FourCharCode, creator: FourCharCode) -> Option<&'static FileKind> {
+    FILE_KINDS.iter().find(|kind| {
+        kind.file_type == file_type
+            && kind
+                .creator
+                .is_none_or(|kind_creator| kind_creator == creator)
+    })
+}
+
+// the first kind with `extension`, ignoring case, so that the most common
+// type for an extension wins
+pub fn lookup_extension(extension: &str) -> Option<&'static FileKind> {
+    FILE_KINDS
+        .iter()
+        .find(|kind| kind.extension.eq_ignore_ascii_case(extension))
+}
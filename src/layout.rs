@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use crate::{
+    decode::{decode_char, hexbin_with_mode, DecodeMode, SixBitDecoder},
+    encode::{EncodeOptions, SixBitEncoder},
+    error::DecodeError,
+    parse::{detect, locate, Format},
+    rle::RLE_MARKER_BYTE,
+    HQXRef, HQX,
+};
+
+// how an encoded file was laid out, beyond the bytes it decodes to, so that
+// it can be encoded again byte for byte
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Layout {
+    // everything before the opening colon, including the marker line
+    pub preamble: Vec<u8>,
+    // everything after the closing colon
+    pub trailer: Vec<u8>,
+    // bytes between encoded characters, such as line endings, along with the
+    // number of characters before them
+    pub breaks: Vec<(usize, Vec<u8>)>,
+    // each run as the offset in the decoded bytes where it starts repeating
+    // and its count byte
+    pub runs: Vec<(usize, u8)>,
+    // whether the stream ended on a marker byte with no count after it
+    pub dangling_marker: bool,
+    pub char_count: usize,
+    // the characters of a trailing partial group, whose unused low bits may
+    // not be zero
+    pub tail: Vec<u8>,
+}
+
+impl Layout {
+    // the most common number of characters between line breaks
+    pub fn line_length(&self) -> Option<usize> {
+        let mut previous = 0;
+        most_common(self.breaks.iter().map(|(at, _)| {
+            let len = at - previous;
+            previous = *at;
+            len
+        }))
+    }
+
+    // the most common line ending
+    pub fn line_ending(&self) -> Option<&[u8]> {
+        most_common(self.breaks.iter().map(|(_, bytes)| &bytes[..]))
+    }
+}
+
+// decodes like `hexbin_with_mode`, also capturing the layout of the input.
+// only 4.0 files can be encoded again, so other formats are rejected
+pub fn hexbin_with_layout(
+    i: &[u8],
+    should_verify: bool,
+    mode: DecodeMode,
+) -> Result<(HQX, Layout), DecodeError> {
+    if detect(i) != Some(Format::BinHex4) {
+        return Err(DecodeError::BadFormat);
+    }
+    let hqx = hexbin_with_mode(i, should_verify, mode)?;
+    let payload = locate(i).ok_or(DecodeError::BadFormat)?;
+
+    let mut layout = Layout {
+        preamble: i[..payload.offset].to_vec(),
+        trailer: i[payload.offset + payload.encoded.len() + 2..].to_vec(),
+        ..Layout::default()
+    };
+
+    let mut chars = Vec::with_capacity(payload.encoded.len());
+    for b in payload.encoded.iter().copied() {
+        if decode_char(b).is_some() {
+            chars.push(b);
+            continue;
+        }
+        match layout.breaks.last_mut() {
+            Some((at, bytes)) if *at == chars.len() => bytes.push(b),
+            _ => layout.breaks.push((chars.len(), vec![b])),
+        }
+    }
+    layout.char_count = chars.len();
+    layout.tail = chars[chars.len() / 4 * 4..].to_vec();
+
+    let mut bytes = SixBitDecoder::default();
+    let compressed: Vec<u8> = chars
+        .iter()
+        .filter_map(|b| bytes.push(decode_char(*b).unwrap()))
+        .collect();
+
+    let mut offset = 0;
+    let mut compressed = compressed.iter().copied();
+    while let Some(byte) = compressed.next() {
+        if byte != RLE_MARKER_BYTE {
+            offset += 1;
+            continue;
+        }
+        match compressed.next() {
+            Some(0) => offset += 1,
+            Some(count) => {
+                layout.runs.push((offset, count));
+                offset += count as usize - 1;
+            }
+            None => layout.dangling_marker = true,
+        }
+    }
+
+    Ok((hqx, layout))
+}
+
+impl<'a> HQXRef<'a> {
+    // reproduces the input that `layout` was captured from; if the file has
+    // changed since, runs and tail that no longer fit are left out
+    pub fn encode_with_layout(&self, layout: &Layout) -> Vec<u8> {
+        let compressed = compress_with_runs(&self.hqx.vec, layout);
+
+        let full_len = layout.char_count / 4 * 3;
+        let partial_len = layout.tail.len().saturating_sub(1);
+        let keep_tail = compressed.len() == full_len + partial_len;
+
+        let options = EncodeOptions {
+            line_length: 0,
+            ..EncodeOptions::default()
+        };
+        let mut encoder = SixBitEncoder::new(&options);
+        let mut chars = Vec::with_capacity(layout.char_count);
+        if keep_tail {
+            for byte in compressed[..full_len].iter().copied() {
+                encoder.push(byte, &mut chars);
+            }
+            chars.extend_from_slice(&layout.tail);
+        } else {
+            for byte in compressed.iter().copied() {
+                encoder.push(byte, &mut chars);
+            }
+            encoder.finish(&mut chars);
+        }
+
+        let mut encoded = Vec::with_capacity(
+            layout.preamble.len() + chars.len() + layout.trailer.len() + layout.breaks.len() * 2,
+        );
+        encoded.extend_from_slice(&layout.preamble);
+        encoded.push(b':');
+
+        let mut written = 0;
+        for (at, bytes) in &layout.breaks {
+            let at = (*at).min(chars.len());
+            encoded.extend_from_slice(&chars[written..at]);
+            encoded.extend_from_slice(bytes);
+            written = at;
+        }
+        encoded.extend_from_slice(&chars[written..]);
+
+        encoded.push(b':');
+        encoded.extend_from_slice(&layout.trailer);
+
+        encoded
+    }
+}
+
+// compresses `bytes` with exactly the runs in `layout`
+fn compress_with_runs(bytes: &[u8], layout: &Layout) -> Vec<u8> {
+    let mut compressed = Vec::with_capacity(bytes.len() + 1);
+    let mut runs = layout.runs.iter().copied().peekable();
+    // like the decoder, repeats zeros if a run comes before any byte
+    let mut previous = 0;
+    let mut offset = 0;
+    loop {
+        while let Some((at, count)) = runs.next_if(|(at, _)| *at <= offset) {
+            let repeated = count as usize - 1;
+            let fits = at == offset
+                && bytes
+                    .get(offset..offset + repeated)
+                    .is_some_and(|run| run.iter().all(|b| *b == previous));
+            if fits {
+                compressed.push(RLE_MARKER_BYTE);
+                compressed.push(count);
+                offset += repeated;
+            }
+        }
+
+        let Some(byte) = bytes.get(offset).copied() else {
+            break;
+        };
+        compressed.push(byte);
+        if byte == RLE_MARKER_BYTE {
+            compressed.push(0);
+        }
+        previous = byte;
+        offset += 1;
+    }
+
+    if layout.dangling_marker {
+        compressed.push(RLE_MARKER_BYTE);
+    }
+
+    compressed
+}
+
+fn most_common<T: Eq + std::hash::Hash, I: Iterator<Item = T>>(items: I) -> Option<T> {
+    let mut counts = HashMap::new();
+    for item in items {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(item, _)| item)
+}
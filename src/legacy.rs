@@ -0,0 +1,275 @@
+// the formats of BinHex 1.0 to 3.0, which predate the 6-bit alphabet:
+//
+//     Read Me                  the file name
+//     #TEXTttxt$0100           type, creator and optionally Finder flags
+//     ***COMPRESSED            only in BinHex 3.0 `.hcx` files
+//     ***DATA FORK
+//     48656C6C6F...            the fork
+//     ***END OF DATA
+//     ***CHECKSUM:01F4         or `***CRC:` after compressed forks
+//     ***RESOURCE FORK
+//     ...
+//
+// `.hex` forks are two hex digits a byte. `.hcx` forks pack three bytes into
+// four characters from ` ` to `_`, each worth its code minus 0x20
+
+use std::ffi::CString;
+
+use crate::{
+    crc::Crc16,
+    encode::binhex,
+    error::{CRCVerificationError, DecodeError},
+    finder::FinderFlags,
+    fourcc::FourCharCode,
+    ForkKind, HQXConfig, HQX,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Header<'a> {
+    pub(crate) name: Option<&'a [u8]>,
+    pub(crate) line: &'a [u8],
+    pub(crate) compressed: bool,
+}
+
+// numbered lines, whichever of CR, LF or CRLF ends them
+fn lines(i: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    i.split(|b| *b == b'\n')
+        .flat_map(|line| {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            line.split(|b| *b == b'\r')
+        })
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+}
+
+fn trim(line: &[u8]) -> &[u8] {
+    line.trim_ascii()
+}
+
+// `#TYPECRTR`, optionally followed by `$FFFF` Finder flags
+fn is_header_line(line: &[u8]) -> bool {
+    match line {
+        [b'#', codes @ ..] if codes.len() == 8 => true,
+        [b'#', _, _, _, _, _, _, _, _, b'$', flags @ ..] => {
+            flags.len() == 4 && flags.iter().all(u8::is_ascii_hexdigit)
+        }
+        _ => false,
+    }
+}
+
+fn is_fork_section(line: &[u8]) -> bool {
+    let line = line.to_ascii_uppercase();
+    line.starts_with(b"***DATA FORK") || line.starts_with(b"***RESOURCE FORK")
+}
+
+// the `#TYPECRTR` line, which must be followed by a fork section, with only
+// `***COMPRESSED` allowed between them
+pub(crate) fn find_header(i: &[u8]) -> Option<Header<'_>> {
+    let lines: Vec<&[u8]> = lines(i)
+        .map(|(_, line)| trim(line))
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    lines.iter().enumerate().find_map(|(index, &line)| {
+        if !is_header_line(line) {
+            return None;
+        }
+        let mut after = lines[index + 1..].iter();
+        let mut section = after.next();
+        let compressed =
+            section.is_some_and(|section| section.eq_ignore_ascii_case(b"***COMPRESSED"));
+        if compressed {
+            section = after.next();
+        }
+        section
+            .is_some_and(|section| is_fork_section(section))
+            .then(|| Header {
+                name: index
+                    .checked_sub(1)
+                    .map(|previous| lines[previous])
+                    .filter(|name| !name.starts_with(b"(This file")),
+                line,
+                compressed,
+            })
+    })
+}
+
+#[derive(Debug, Default)]
+struct ForkState {
+    bytes: Vec<u8>,
+    // values of a trailing partial group in compressed forks
+    pending: Vec<u8>,
+}
+
+impl ForkState {
+    // the fork with the whole bytes of a trailing partial group
+    fn finished(&self) -> Vec<u8> {
+        let mut bytes = self.bytes.clone();
+        if let [a, b, rest @ ..] = &self.pending[..] {
+            bytes.push(a << 2 | b >> 4);
+            if let [c, ..] = rest {
+                bytes.push(b << 4 | c >> 2);
+            }
+        }
+        bytes
+    }
+}
+
+pub(crate) fn hexbin(i: &[u8], should_verify: bool) -> Result<HQX, DecodeError> {
+    let header = find_header(i).ok_or(DecodeError::BadFormat)?;
+
+    let line = header.line;
+    let file_type = FourCharCode(line[1..5].try_into().unwrap());
+    let author = FourCharCode(line[5..9].try_into().unwrap());
+    let flags = match line.get(9..) {
+        Some([b'$', digits @ ..]) => parse_hex_u16(digits)
+            .map(FinderFlags::from)
+            .ok_or(DecodeError::BadFormat)?,
+        _ => FinderFlags::default(),
+    };
+
+    let mut data = ForkState::default();
+    let mut resource = ForkState::default();
+    let mut current: Option<ForkKind> = None;
+    let mut last: Option<ForkKind> = None;
+    let mut past_header = false;
+
+    for (line_number, line) in lines(i) {
+        if !past_header {
+            past_header = trim(line) == header.line;
+            continue;
+        }
+
+        let trimmed = trim(line);
+        if let Some(keyword) = trimmed.strip_prefix(b"***") {
+            let keyword = keyword.to_ascii_uppercase();
+            if keyword.starts_with(b"DATA") {
+                current = Some(ForkKind::Data);
+            } else if keyword.starts_with(b"RESOURCE") {
+                current = Some(ForkKind::Resource);
+            } else if keyword.starts_with(b"END") {
+                last = current.take();
+            } else if let Some(sum) = keyword.strip_prefix(b"CHECKSUM:") {
+                check(last, &data, &resource, sum, should_verify, checksum)?;
+            } else if let Some(crc) = keyword.strip_prefix(b"CRC:") {
+                check(last, &data, &resource, crc, should_verify, Crc16::checksum)?;
+            }
+            continue;
+        }
+
+        let fork = match current {
+            Some(ForkKind::Data) => &mut data,
+            Some(ForkKind::Resource) => &mut resource,
+            None => continue,
+        };
+        if header.compressed {
+            push_compressed(line, line_number, fork)?;
+        } else {
+            push_hex(line, line_number, fork)?;
+        }
+    }
+
+    let name = header
+        .name
+        .map(|name| CString::new(name).map_err(|_| DecodeError::BadFormat))
+        .transpose()?;
+    binhex(HQXConfig {
+        name,
+        file_type: Some(file_type),
+        author: Some(author),
+        flags: Some(flags),
+        data: Some(&data.finished()),
+        resource: Some(&resource.finished()),
+    })
+    .map_err(|_| DecodeError::BadFormat)
+}
+
+fn push_hex(line: &[u8], line_number: usize, fork: &mut ForkState) -> Result<(), DecodeError> {
+    let mut high = None;
+    for (column, b) in line.iter().copied().enumerate() {
+        if b.is_ascii_whitespace() {
+            continue;
+        }
+        let digit = hex_digit(b).ok_or(DecodeError::IllegalCharacter {
+            byte: b,
+            line: line_number,
+            column: column + 1,
+        })?;
+        match high.take() {
+            Some(high) => fork.bytes.push(high << 4 | digit),
+            None => high = Some(digit),
+        }
+    }
+    Ok(())
+}
+
+fn push_compressed(
+    line: &[u8],
+    line_number: usize,
+    fork: &mut ForkState,
+) -> Result<(), DecodeError> {
+    for (column, b) in line.iter().copied().enumerate() {
+        let value = match b {
+            0x20..=0x5F => b - 0x20,
+            // some mailers and uuencode write zero as a backquote
+            b'`' => 0,
+            _ => {
+                return Err(DecodeError::IllegalCharacter {
+                    byte: b,
+                    line: line_number,
+                    column: column + 1,
+                })
+            }
+        };
+        fork.pending.push(value);
+        if fork.pending.len() == 4 {
+            let [a, b, c, d] = [0, 1, 2, 3].map(|k| fork.pending[k]);
+            fork.bytes
+                .extend_from_slice(&[a << 2 | b >> 4, b << 4 | c >> 2, c << 6 | d]);
+            fork.pending.clear();
+        }
+    }
+    Ok(())
+}
+
+fn check(
+    fork: Option<ForkKind>,
+    data: &ForkState,
+    resource: &ForkState,
+    expected: &[u8],
+    should_verify: bool,
+    compute: fn(&[u8]) -> u16,
+) -> Result<(), DecodeError> {
+    if !should_verify {
+        return Ok(());
+    }
+    let (fork, err) = match fork {
+        Some(ForkKind::Data) => (data, CRCVerificationError::Data),
+        Some(ForkKind::Resource) => (resource, CRCVerificationError::Resource),
+        // a checksum outside of any fork
+        None => return Ok(()),
+    };
+
+    let expected = parse_hex_u16(expected.trim_ascii()).ok_or(DecodeError::BadFormat)?;
+    if compute(&fork.finished()) == expected {
+        Ok(())
+    } else {
+        Err(DecodeError::CRCVerificationError(err))
+    }
+}
+
+// the 16-bit sum of the bytes that BinHex 2.0 writes after each fork
+fn checksum(bytes: &[u8]) -> u16 {
+    bytes
+        .iter()
+        .fold(0u16, |sum, b| sum.wrapping_add(*b as u16))
+}
+
+fn hex_digit(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|digit| digit as u8)
+}
+
+fn parse_hex_u16(digits: &[u8]) -> Option<u16> {
+    let digits = std::str::from_utf8(digits).ok()?;
+    u16::from_str_radix(digits.get(..4.min(digits.len()))?, 16).ok()
+}
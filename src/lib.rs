@@ -1,15 +1,41 @@
+pub mod applefile;
+pub mod builder;
+pub mod crc;
 pub mod decode;
 pub mod encode;
-mod error;
-mod parse;
-mod verify;
-
+pub mod error;
+pub mod extract;
+pub mod filename;
+pub mod finder;
+pub mod fourcc;
+pub mod layout;
+mod legacy;
+pub mod macbinary;
+pub mod macroman;
+#[cfg(feature = "mime")]
+pub mod mime;
+pub mod multipart;
+pub mod parse;
+pub mod reader;
+pub mod resource;
+mod rle;
+pub mod sidecar;
+pub mod sixbit;
+pub mod verify;
+pub mod writer;
+
+use builder::HqxBuilder;
+use crc::Crc16;
 use encode::binhex;
-use error::EncodeError;
+use error::{EncodeError, ParseError};
+use filename::host_file_name;
+use finder::FinderFlags;
+use fourcc::FourCharCode;
 
-use std::io::{ErrorKind, Write};
+use std::io::Write;
 use std::{
     ffi::{CStr, CString},
+    fmt,
     fs::File,
     path::Path,
 };
@@ -24,117 +50,30 @@ impl HQX {
         HQX { vec }
     }
 
-    pub fn borrow(&self) -> HQXRef {
-        let mut bytes = &self.vec[..];
-
-        let name_len = &bytes[0];
-        bytes = &bytes[1..];
-
-        let name_len_usize = *name_len as usize;
-
-        // add one to include null terminator
-        let name = CStr::from_bytes_with_nul(&bytes[..name_len_usize + 1]).unwrap();
-        bytes = &bytes[name_len_usize + 1..];
-
-        let file_type = <&[u8; 4]>::try_from(&bytes[..4]).unwrap();
-        bytes = &bytes[4..];
-
-        let author = <&[u8; 4]>::try_from(&bytes[..4]).unwrap();
-        bytes = &bytes[4..];
-
-        let flags = <&[u8; 2]>::try_from(&bytes[..2]).unwrap();
-        bytes = &bytes[2..];
-
-        let data_len = u32::from_be_bytes(<[u8; 4]>::try_from(&bytes[..4]).unwrap());
-        bytes = &bytes[4..];
-
-        let resource_len = u32::from_be_bytes(<[u8; 4]>::try_from(&bytes[..4]).unwrap());
-        bytes = &bytes[4..];
-
-        let hc_bytes = <[u8; 2]>::try_from(&bytes[..2]).unwrap_or_default();
-        bytes = &bytes[2..];
-
-        let hc = u16::from_be_bytes(hc_bytes);
-
-        let header_len = get_header_len(name_len_usize) as u16;
-
-        let data = {
-            if data_len > 0 {
-                let data = &bytes[..data_len as usize];
-                bytes = &bytes[data_len as usize..];
-
-                Some(data)
-            } else {
-                None
-            }
-        };
-
-        let dc_bytes = {
-            if bytes.len() >= 2 {
-                let dc_bytes = <[u8; 2]>::try_from(&bytes[..2]).unwrap_or_default();
-                bytes = &bytes[2..];
-                dc_bytes
-            } else {
-                [0, 0]
-            }
-        };
-
-        let dc = u16::from_be_bytes(dc_bytes);
-        let data_fork = data.map(|data| Fork { data, crc: dc });
-
-        let resource = {
-            if resource_len > 0 {
-                let resource = &bytes[..resource_len as usize];
-                bytes = &bytes[resource_len as usize..];
-
-                Some(resource)
-            } else {
-                None
-            }
-        };
-
-        let rc_bytes = {
-            if bytes.len() >= 2 {
-                let rc_bytes = <[u8; 2]>::try_from(&bytes[..2]).unwrap_or_default();
-                bytes = &bytes[2..];
-                rc_bytes
-            } else {
-                [0, 0]
-            }
-        };
-
-        let rc = u16::from_be_bytes(rc_bytes);
-        let resource_fork = resource.map(|resource| Fork {
-            data: resource,
-            crc: rc,
-        });
+    // panics if the buffer is not a well-formed header followed by its forks;
+    // use `try_borrow` for buffers that did not come from this crate
+    pub fn borrow(&self) -> HQXRef<'_> {
+        self.try_borrow().expect("malformed HQX buffer")
+    }
 
-        HQXRef {
-            hqx: self,
-            name_len,
-            name,
-            file_type,
-            author,
-            flags,
-            data_len,
-            resource_len,
-            hc,
-            header_len,
-            data_fork,
-            resource_fork,
-        }
+    pub fn try_borrow(&self) -> Result<HQXRef<'_>, ParseError> {
+        HQXRef::parse(self)
     }
 
     pub fn from_config(config: HQXConfig) -> Result<HQX, EncodeError> {
         binhex(config)
     }
+
+    pub fn builder<'a>() -> HqxBuilder<'a> {
+        HqxBuilder::new()
+    }
 }
 
 pub struct HQXConfig<'a> {
     pub name: Option<CString>,
-    pub file_type: Option<&'a [u8; 4]>,
-    pub author: Option<&'a [u8; 4]>,
-    pub flags: Option<&'a [u8; 2]>,
+    pub file_type: Option<FourCharCode>,
+    pub author: Option<FourCharCode>,
+    pub flags: Option<FinderFlags>,
     pub data: Option<&'a [u8]>,
     pub resource: Option<&'a [u8]>,
 }
@@ -151,9 +90,9 @@ pub struct HQXRef<'a> {
     pub hqx: &'a HQX,
     pub name_len: &'a u8,
     pub name: &'a CStr,
-    pub file_type: &'a [u8; 4],
-    pub author: &'a [u8; 4],
-    pub flags: &'a [u8; 2],
+    pub file_type: FourCharCode,
+    pub author: FourCharCode,
+    pub flags: FinderFlags,
     pub data_len: u32,
     pub resource_len: u32,
     pub hc: u16,
@@ -168,46 +107,105 @@ pub struct Fork<'a> {
     pub crc: u16,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkKind {
+    Data,
+    Resource,
+}
+
+impl fmt::Display for ForkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForkKind::Data => write!(f, "data"),
+            ForkKind::Resource => write!(f, "resource"),
+        }
+    }
+}
+
 impl<'a> HQXRef<'a> {
+    pub fn parse(hqx: &'a HQX) -> Result<HQXRef<'a>, ParseError> {
+        let bytes = &hqx.vec[..];
+
+        let name_len = bytes.first().ok_or(ParseError::TruncatedHeader {
+            len: 0,
+            expected: get_header_len(0),
+        })?;
+        let name_len_usize = *name_len as usize;
+
+        let header_len = get_header_len(name_len_usize);
+        if bytes.len() < header_len {
+            return Err(ParseError::TruncatedHeader {
+                len: bytes.len(),
+                expected: header_len,
+            });
+        }
+
+        // add one to include null terminator
+        let name = CStr::from_bytes_with_nul(&bytes[1..name_len_usize + 2])
+            .map_err(|_| ParseError::BadName { offset: 1 })?;
+
+        // the length check above guarantees the fixed size fields are present
+        let mut offset = name_len_usize + 2;
+        let mut take = |len: usize| {
+            let field = &bytes[offset..offset + len];
+            offset += len;
+            field
+        };
+
+        let file_type = FourCharCode(<[u8; 4]>::try_from(take(4)).unwrap());
+        let author = FourCharCode(<[u8; 4]>::try_from(take(4)).unwrap());
+        let flags = FinderFlags::from_bytes(<[u8; 2]>::try_from(take(2)).unwrap());
+        let data_len = u32::from_be_bytes(<[u8; 4]>::try_from(take(4)).unwrap());
+        let resource_len = u32::from_be_bytes(<[u8; 4]>::try_from(take(4)).unwrap());
+        let hc = u16::from_be_bytes(<[u8; 2]>::try_from(take(2)).unwrap());
+
+        let mut offset = header_len;
+        let data_fork = parse_fork(bytes, &mut offset, data_len, ForkKind::Data)?;
+        let resource_fork = parse_fork(bytes, &mut offset, resource_len, ForkKind::Resource)?;
+
+        Ok(HQXRef {
+            hqx,
+            name_len,
+            name,
+            file_type,
+            author,
+            flags,
+            data_len,
+            resource_len,
+            hc,
+            header_len: header_len as u16,
+            data_fork,
+            resource_fork,
+        })
+    }
+
+    pub fn unicode_name(&self) -> String {
+        macroman::decode_name(self.name)
+    }
+
+    // writes the data fork alone
     pub fn decode_to_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<File> {
         let path = {
             let mut path = path.as_ref().to_path_buf();
             if path.is_dir() {
-                match self.name.to_str() {
-                    Ok(name) => {
-                        path.push(name);
-                    }
-                    Err(err) => {
-                        eprintln!("{:#?}", err);
-                        return Err(std::io::Error::new(ErrorKind::Other, "Non UTF-8 file name"));
-                    }
-                }
+                path.push(host_file_name(self.name));
             }
             path
         };
         let mut file = File::create(path)?;
 
+        // an empty file for resource-only files; `extract` keeps the rest
         if let Some(Fork { data, .. }) = self.data_fork.as_ref() {
             file.write_all(data)?;
-            Ok(file)
-        } else {
-            Err(ErrorKind::InvalidData.into())
         }
+        Ok(file)
     }
 
     pub fn encode_to_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<File> {
         let path = {
             let mut path = path.as_ref().to_path_buf();
             if path.is_dir() {
-                match self.name.to_str() {
-                    Ok(name) => {
-                        path.push(name);
-                    }
-                    Err(err) => {
-                        eprintln!("{:#?}", err);
-                        return Err(std::io::Error::new(ErrorKind::Other, "Non UTF-8 file name"));
-                    }
-                }
+                path.push(host_file_name(self.name));
             }
             path
         };
@@ -221,6 +219,76 @@ impl<'a> HQXRef<'a> {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct HQXHeader {
+    pub name: CString,
+    pub file_type: FourCharCode,
+    pub author: FourCharCode,
+    pub flags: FinderFlags,
+    pub data_len: u32,
+    pub resource_len: u32,
+    pub hc: u16,
+}
+
+impl HQXHeader {
+    // serializes the header, computing the header crc rather than using `hc`
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let name_bytes = self.name.as_bytes();
+        let name_len: u8 = name_bytes
+            .len()
+            .try_into()
+            .map_err(|_| EncodeError::FileNameTooLong)?;
+
+        let mut bytes = Vec::with_capacity(get_header_len(name_len.into()));
+        bytes.push(name_len);
+        bytes.extend_from_slice(name_bytes);
+        bytes.push(0); // null-terminate name
+        bytes.extend_from_slice(self.file_type.as_bytes());
+        bytes.extend_from_slice(self.author.as_bytes());
+        bytes.extend_from_slice(&self.flags.sanitized().to_bytes());
+        bytes.extend_from_slice(&self.data_len.to_be_bytes());
+        bytes.extend_from_slice(&self.resource_len.to_be_bytes());
+
+        let hc = Crc16::checksum(&bytes);
+        bytes.extend_from_slice(&hc.to_be_bytes());
+
+        Ok(bytes)
+    }
+}
+
+fn parse_fork<'a>(
+    bytes: &'a [u8],
+    offset: &mut usize,
+    len: u32,
+    fork: ForkKind,
+) -> Result<Option<Fork<'a>>, ParseError> {
+    let start = *offset;
+    let available = bytes.len() - start;
+
+    let data = bytes
+        .get(start..)
+        .and_then(|rest| rest.get(..len as usize))
+        .ok_or(ParseError::ForkOutOfBounds {
+            fork,
+            offset: start,
+            len,
+            available,
+        })?;
+    let crc_start = start + data.len();
+
+    let crc_bytes = bytes
+        .get(crc_start..crc_start + 2)
+        .ok_or(ParseError::MissingCRC {
+            fork,
+            offset: crc_start,
+        })?;
+    *offset = crc_start + 2;
+
+    let crc = u16::from_be_bytes(<[u8; 2]>::try_from(crc_bytes).unwrap());
+
+    Ok((len > 0).then_some(Fork { data, crc }))
+}
+
 fn get_header_len(name_len: usize) -> usize {
     1 + (name_len + 1) + 4 + 4 + 2 + 4 + 4 + 2
 }
@@ -0,0 +1,258 @@
+use std::ffi::CString;
+
+use crate::{
+    crc::Crc16,
+    encode::binhex,
+    error::{EncodeError, MacBinaryError},
+    finder::FinderFlags,
+    fourcc::FourCharCode,
+    HQXConfig, HQXRef, HQX,
+};
+
+const HEADER_LEN: usize = 128;
+const MAX_NAME_LEN: usize = 63;
+const SIGNATURE: &[u8; 4] = b"mBIN";
+const VERSION_II: u8 = 129;
+const VERSION_III: u8 = 130;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MacBinaryVersion {
+    I,
+    II,
+    III,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacBinary<'a> {
+    pub version: MacBinaryVersion,
+    pub name: &'a [u8],
+    pub file_type: FourCharCode,
+    pub author: FourCharCode,
+    pub flags: FinderFlags,
+    // window position of the icon and the folder that contains it
+    pub vertical: u16,
+    pub horizontal: u16,
+    pub folder: u16,
+    pub protected: bool,
+    // seconds since 1904-01-01
+    pub created: u32,
+    pub modified: u32,
+    pub script: u8,
+    pub extended_flags: u8,
+    pub data: &'a [u8],
+    pub resource: &'a [u8],
+    pub comment: &'a [u8],
+}
+
+impl<'a> MacBinary<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<MacBinary<'a>, MacBinaryError> {
+        let header = bytes
+            .get(..HEADER_LEN)
+            .ok_or(MacBinaryError::NotMacBinary)?;
+
+        let name_len = header[1] as usize;
+        if header[0] != 0 || header[74] != 0 || !(1..=MAX_NAME_LEN).contains(&name_len) {
+            return Err(MacBinaryError::NotMacBinary);
+        }
+
+        let crc = be_u16(header, 124);
+        let version = if &header[102..106] == SIGNATURE {
+            MacBinaryVersion::III
+        } else if crc != 0 && crc == header_crc(header) {
+            MacBinaryVersion::II
+        } else if header[82] == 0 {
+            MacBinaryVersion::I
+        } else {
+            return Err(MacBinaryError::NotMacBinary);
+        };
+
+        let data_len = be_u32(header, 83) as usize;
+        let resource_len = be_u32(header, 87) as usize;
+
+        let (secondary_len, comment_len, flags_low) = match version {
+            MacBinaryVersion::I => (0, 0, 0),
+            _ => (
+                be_u16(header, 120) as usize,
+                be_u16(header, 99) as usize,
+                header[101],
+            ),
+        };
+
+        let mut offset = HEADER_LEN + padded_len(secondary_len);
+        let mut take = |len: usize| {
+            let fork = bytes
+                .get(offset..)
+                .and_then(|rest| rest.get(..len))
+                .ok_or(MacBinaryError::Truncated)?;
+            offset += padded_len(len);
+            Ok(fork)
+        };
+
+        let data = take(data_len)?;
+        let resource = take(resource_len)?;
+        // the comment is often left off by writers that set its length
+        let comment = take(comment_len).unwrap_or_default();
+
+        Ok(MacBinary {
+            version,
+            name: &header[2..2 + name_len],
+            file_type: FourCharCode(header[65..69].try_into().unwrap()),
+            author: FourCharCode(header[69..73].try_into().unwrap()),
+            flags: FinderFlags::from_bytes([header[73], flags_low]),
+            vertical: be_u16(header, 75),
+            horizontal: be_u16(header, 77),
+            folder: be_u16(header, 79),
+            protected: header[81] & 0x01 != 0,
+            created: be_u32(header, 91),
+            modified: be_u32(header, 95),
+            script: header[106],
+            extended_flags: header[107],
+            data,
+            resource,
+            comment,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MacBinaryError> {
+        if self.name.is_empty() || self.name.len() > MAX_NAME_LEN {
+            return Err(MacBinaryError::BadName);
+        }
+        let data_len: u32 = self
+            .data
+            .len()
+            .try_into()
+            .map_err(|_| MacBinaryError::ForkTooLarge)?;
+        let resource_len: u32 = self
+            .resource
+            .len()
+            .try_into()
+            .map_err(|_| MacBinaryError::ForkTooLarge)?;
+
+        let mut header = [0u8; HEADER_LEN];
+        header[1] = self.name.len() as u8;
+        header[2..2 + self.name.len()].copy_from_slice(self.name);
+        header[65..69].copy_from_slice(self.file_type.as_bytes());
+        header[69..73].copy_from_slice(self.author.as_bytes());
+        let flags = self.flags.to_bytes();
+        header[73] = flags[0];
+        header[75..77].copy_from_slice(&self.vertical.to_be_bytes());
+        header[77..79].copy_from_slice(&self.horizontal.to_be_bytes());
+        header[79..81].copy_from_slice(&self.folder.to_be_bytes());
+        header[81] = self.protected as u8;
+        header[83..87].copy_from_slice(&data_len.to_be_bytes());
+        header[87..91].copy_from_slice(&resource_len.to_be_bytes());
+        header[91..95].copy_from_slice(&self.created.to_be_bytes());
+        header[95..99].copy_from_slice(&self.modified.to_be_bytes());
+
+        let comment = match self.version {
+            MacBinaryVersion::I => &[][..],
+            _ => self.comment,
+        };
+
+        if self.version >= MacBinaryVersion::II {
+            let comment_len: u16 = comment
+                .len()
+                .try_into()
+                .map_err(|_| MacBinaryError::ForkTooLarge)?;
+            header[99..101].copy_from_slice(&comment_len.to_be_bytes());
+            header[101] = flags[1];
+            header[122] = if self.version == MacBinaryVersion::III {
+                VERSION_III
+            } else {
+                VERSION_II
+            };
+            header[123] = VERSION_II;
+        }
+
+        if self.version == MacBinaryVersion::III {
+            header[102..106].copy_from_slice(SIGNATURE);
+            header[106] = self.script;
+            header[107] = self.extended_flags;
+        }
+
+        if self.version >= MacBinaryVersion::II {
+            let crc = header_crc(&header);
+            header[124..126].copy_from_slice(&crc.to_be_bytes());
+        }
+
+        let mut bytes = Vec::with_capacity(
+            HEADER_LEN
+                + padded_len(self.data.len())
+                + padded_len(self.resource.len())
+                + padded_len(comment.len()),
+        );
+        bytes.extend_from_slice(&header);
+        for section in [self.data, self.resource, comment] {
+            bytes.extend_from_slice(section);
+            bytes.resize(bytes.len() + padded_len(section.len()) - section.len(), 0);
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn to_hqx(&self) -> Result<HQX, EncodeError> {
+        let name = CString::new(self.name).map_err(|_| EncodeError::BadFileName)?;
+        binhex(HQXConfig {
+            name: Some(name),
+            file_type: Some(self.file_type),
+            author: Some(self.author),
+            flags: Some(self.flags),
+            data: Some(self.data),
+            resource: Some(self.resource),
+        })
+    }
+}
+
+impl<'a> From<&HQXRef<'a>> for MacBinary<'a> {
+    fn from(hqx_ref: &HQXRef<'a>) -> MacBinary<'a> {
+        MacBinary {
+            version: MacBinaryVersion::III,
+            name: hqx_ref.name.to_bytes(),
+            file_type: hqx_ref.file_type,
+            author: hqx_ref.author,
+            flags: hqx_ref.flags,
+            vertical: 0,
+            horizontal: 0,
+            folder: 0,
+            protected: false,
+            created: 0,
+            modified: 0,
+            script: 0,
+            extended_flags: 0,
+            data: hqx_ref
+                .data_fork
+                .as_ref()
+                .map(|fork| fork.data)
+                .unwrap_or_default(),
+            resource: hqx_ref
+                .resource_fork
+                .as_ref()
+                .map(|fork| fork.data)
+                .unwrap_or_default(),
+            comment: &[],
+        }
+    }
+}
+
+impl<'a> HQXRef<'a> {
+    pub fn to_macbinary(&self) -> Result<Vec<u8>, MacBinaryError> {
+        MacBinary::from(self).to_bytes()
+    }
+}
+
+// the crc covers the header up to the crc field itself
+fn header_crc(header: &[u8]) -> u16 {
+    Crc16::checksum(&header[..124])
+}
+
+fn padded_len(len: usize) -> usize {
+    len.div_ceil(HEADER_LEN) * HEADER_LEN
+}
+
+fn be_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
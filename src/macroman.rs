@@ -0,0 +1,51 @@
+use std::ffi::{CStr, CString};
+
+use crate::error::EncodeError;
+
+// Unicode equivalents of Mac OS Roman 0x80..=0xFF; the low half is ASCII
+const HIGH_CHARS: [char; 128] = [
+    'Ä', 'Å', 'Ç', 'É', 'Ñ', 'Ö', 'Ü', 'á', 'à', 'â', 'ä', 'ã', 'å', 'ç', 'é', 'è', //
+    'ê', 'ë', 'í', 'ì', 'î', 'ï', 'ñ', 'ó', 'ò', 'ô', 'ö', 'õ', 'ú', 'ù', 'û', 'ü', //
+    '†', '°', '¢', '£', '§', '•', '¶', 'ß', '®', '©', '™', '´', '¨', '≠', 'Æ', 'Ø', //
+    '∞', '±', '≤', '≥', '¥', 'µ', '∂', '∑', '∏', 'π', '∫', 'ª', 'º', 'Ω', 'æ', 'ø', //
+    '¿', '¡', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{A0}', 'À', 'Ã', 'Õ', 'Œ', 'œ', //
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ÿ', 'Ÿ', '⁄', '€', '‹', '›', 'ﬁ', 'ﬂ', //
+    '‡', '·', '‚', '„', '‰', 'Â', 'Ê', 'Á', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', 'Ó', 'Ô', //
+    '\u{F8FF}', 'Ò', 'Ú', 'Û', 'Ù', 'ı', 'ˆ', '˜', '¯', '˘', '˙', '˚', '¸', '˝', '˛', 'ˇ', //
+];
+
+pub fn decode_char(byte: u8) -> char {
+    if byte < 0x80 {
+        byte as char
+    } else {
+        HIGH_CHARS[(byte - 0x80) as usize]
+    }
+}
+
+pub fn encode_char(c: char) -> Option<u8> {
+    if c.is_ascii() {
+        return Some(c as u8);
+    }
+    HIGH_CHARS
+        .iter()
+        .position(|high| *high == c)
+        .map(|i| i as u8 + 0x80)
+}
+
+pub fn decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| decode_char(*b)).collect()
+}
+
+// `None` if the string has characters with no Mac OS Roman equivalent
+pub fn encode(s: &str) -> Option<Vec<u8>> {
+    s.chars().map(encode_char).collect()
+}
+
+pub fn decode_name(name: &CStr) -> String {
+    decode(name.to_bytes())
+}
+
+pub fn encode_name(name: &str) -> Result<CString, EncodeError> {
+    let bytes = encode(name).ok_or(EncodeError::NameNotMacRoman)?;
+    CString::new(bytes).map_err(|_| EncodeError::BadFileName)
+}
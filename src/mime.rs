@@ -0,0 +1,298 @@
+use crate::{decode::hexbin_all, error::DecodeError, parse::find_marker, HQX};
+
+const BINHEX_TYPES: &[&str] = &[
+    "application/mac-binhex40",
+    "application/mac-binhex",
+    "application/binhex",
+    "application/x-binhex40",
+];
+
+// multipart and message/rfc822 entities nested deeper than this are reported
+// as an error instead of walked
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug)]
+pub struct Attachment {
+    pub message_id: Option<String>,
+    // the `filename` or `name` parameter of the part, if it has one
+    pub file_name: Option<String>,
+    pub hqx: Result<HQX, DecodeError>,
+}
+
+// finds the BinHex attachments and inline BinHex bodies of an RFC 5322 message
+pub fn extract_message(message: &[u8], should_verify: bool) -> Vec<Attachment> {
+    let mut attachments = Vec::new();
+    walk_entity(message, None, should_verify, 0, &mut attachments);
+    attachments
+}
+
+// does the same for every message of an mbox file
+pub fn extract_mbox(mbox: &[u8], should_verify: bool) -> impl Iterator<Item = Attachment> + '_ {
+    mbox_messages(mbox).flat_map(move |message| extract_message(&message, should_verify))
+}
+
+// splits an mbox file at its `From ` lines, undoing `>From ` quoting
+fn mbox_messages(mbox: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let mut lines = split_lines(mbox).peekable();
+    std::iter::from_fn(move || {
+        // skip the separator line, and anything before the first one
+        for line in lines.by_ref() {
+            if line.starts_with(b"From ") {
+                break;
+            }
+        }
+        lines.peek()?;
+
+        // a `From ` line only starts a new message after a blank line
+        let mut message = Vec::new();
+        let mut after_blank = false;
+        while let Some(line) = lines.next_if(|line| !(after_blank && line.starts_with(b"From "))) {
+            after_blank = trim_line_ending(line).is_empty();
+            let quoted = line.iter().take_while(|b| **b == b'>').count();
+            if quoted > 0 && line[quoted..].starts_with(b"From ") {
+                message.extend_from_slice(&line[1..]);
+            } else {
+                message.extend_from_slice(line);
+            }
+        }
+        Some(message)
+    })
+}
+
+fn walk_entity(
+    entity: &[u8],
+    message_id: Option<&str>,
+    should_verify: bool,
+    depth: usize,
+    attachments: &mut Vec<Attachment>,
+) {
+    let (headers, body) = split_headers(entity);
+    let message_id = header(&headers, "message-id")
+        .map(|id| id.trim().to_string())
+        .or(message_id.map(String::from));
+
+    if depth > MAX_DEPTH {
+        attachments.push(Attachment {
+            message_id,
+            file_name: None,
+            hqx: Err(DecodeError::NestedTooDeeply),
+        });
+        return;
+    }
+
+    let content_type = header(&headers, "content-type").unwrap_or("text/plain");
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    if media_type.starts_with("multipart/") {
+        if let Some(boundary) = parameter(content_type, "boundary") {
+            for part in multipart_parts(body, boundary.as_bytes()) {
+                walk_entity(
+                    part,
+                    message_id.as_deref(),
+                    should_verify,
+                    depth + 1,
+                    attachments,
+                );
+            }
+        }
+        return;
+    }
+
+    let encoding = header(&headers, "content-transfer-encoding").unwrap_or_default();
+    let body = decode_transfer_encoding(body, encoding);
+
+    if media_type == "message/rfc822" {
+        walk_entity(
+            &body,
+            message_id.as_deref(),
+            should_verify,
+            depth + 1,
+            attachments,
+        );
+        return;
+    }
+
+    let is_binhex = BINHEX_TYPES.contains(&media_type.as_str());
+    let is_inline = media_type.starts_with("text/") && find_marker(&body, 0).is_some();
+    if !is_binhex && !is_inline {
+        return;
+    }
+
+    let file_name = header(&headers, "content-disposition")
+        .and_then(|disposition| parameter(disposition, "filename"))
+        .or_else(|| parameter(content_type, "name"));
+
+    for (_, hqx) in hexbin_all(&body, should_verify) {
+        attachments.push(Attachment {
+            message_id: message_id.clone(),
+            file_name: file_name.clone(),
+            hqx,
+        });
+    }
+}
+
+// unfolded `(name, value)` pairs and the body after the blank line
+fn split_headers(entity: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut offset = 0;
+    for line in split_lines(entity) {
+        offset += line.len();
+        let line = trim_line_ending(line);
+        if line.is_empty() {
+            return (headers, &entity[offset..]);
+        }
+
+        let line = String::from_utf8_lossy(line);
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    // no blank line, so it is all headers
+    (headers, &[])
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+// the value of a `name=value` or `name="value"` parameter of a header
+fn parameter(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| {
+            let value = value.trim();
+            value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value)
+                .to_string()
+        })
+    })
+}
+
+// the bodies between `--boundary` lines, up to the closing `--boundary--`
+fn multipart_parts<'a>(body: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut delimiter = b"--".to_vec();
+    delimiter.extend_from_slice(boundary);
+
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in split_lines(body) {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = trim_line_ending(line).trim_ascii_end();
+        let Some(rest) = trimmed.strip_prefix(&delimiter[..]) else {
+            continue;
+        };
+        if let Some(start) = start {
+            parts.push(&body[start..line_start]);
+        }
+        if rest.starts_with(b"--") {
+            break;
+        }
+        start = Some(offset);
+    }
+
+    parts
+}
+
+fn decode_transfer_encoding(body: &[u8], encoding: &str) -> Vec<u8> {
+    match encoding.trim().to_ascii_lowercase().as_str() {
+        "quoted-printable" => decode_quoted_printable(body),
+        "base64" => decode_base64(body),
+        _ => body.to_vec(),
+    }
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        if body[i] != b'=' {
+            decoded.push(body[i]);
+            i += 1;
+            continue;
+        }
+
+        let rest = &body[i + 1..];
+        // a soft line break, which may have picked up trailing whitespace
+        let padding = rest
+            .iter()
+            .take_while(|b| **b == b' ' || **b == b'\t')
+            .count();
+        match &rest[padding..] {
+            [b'\r', b'\n', ..] => i += 1 + padding + 2,
+            [b'\n', ..] | [b'\r', ..] => i += 1 + padding + 1,
+            [high, low, ..] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                decoded.push(hex_value(*high) << 4 | hex_value(*low));
+                i += 3;
+            }
+            // not an escape, so keep it as it is
+            _ => {
+                decoded.push(b'=');
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+fn decode_base64(body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for b in body {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            // line breaks and anything else a mailer may have added
+            _ => continue,
+        };
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            decoded.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+    decoded
+}
+
+// lines with their line endings, so that offsets add up
+fn split_lines(i: &[u8]) -> impl Iterator<Item = &[u8]> {
+    i.split_inclusive(|b| *b == b'\n')
+}
+
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use crate::{
+    decode::{decode_char, hexbin_with_mode, DecodeMode},
+    encode::BINHEX_FILE_MARKER,
+    error::DecodeError,
+    HQX,
+};
+
+// `missing` lists at most this many more numbers than there are parts, however
+// many parts a header claims
+const MAX_MISSING: usize = 256;
+
+// where a part came from and what its header line claimed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartInfo {
+    // index of the input buffer, or of the segment of a concatenated buffer
+    pub index: usize,
+    pub number: Option<u32>,
+    pub total: Option<u32>,
+    // encoded lines kept from the part
+    pub lines: usize,
+}
+
+// the encoded stream of a split posting, joined back together in part order
+#[derive(Debug, Clone)]
+pub struct Reassembly {
+    pub parts: Vec<PartInfo>,
+    pub missing: Vec<u32>,
+    // parts that came after a part with a higher number
+    pub out_of_order: Vec<u32>,
+    // later copies of a part number; only the first copy is used
+    pub duplicates: Vec<u32>,
+    encoded: Vec<u8>,
+}
+
+impl Reassembly {
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    // the joined stream as a plain BinHex 4.0 file
+    pub fn encoded(&self) -> &[u8] {
+        &self.encoded
+    }
+
+    pub fn decode(&self, should_verify: bool) -> Result<HQX, DecodeError> {
+        self.decode_with_mode(should_verify, DecodeMode::default())
+    }
+
+    pub fn decode_with_mode(
+        &self,
+        should_verify: bool,
+        mode: DecodeMode,
+    ) -> Result<HQX, DecodeError> {
+        hexbin_with_mode(&self.encoded, should_verify, mode)
+    }
+}
+
+// reassembles parts that were saved one per buffer
+pub fn reassemble<B: AsRef<[u8]>>(parts: &[B]) -> Reassembly {
+    let input_len = parts.iter().map(|part| part.as_ref().len()).sum();
+    let segments = parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let lines = lines(part.as_ref());
+            let (number, total) = lines
+                .iter()
+                .find_map(|line| part_header(line, input_len))
+                .map_or((None, None), |(number, total)| (Some(number), Some(total)));
+            Segment {
+                index,
+                number,
+                total,
+                lines,
+            }
+        })
+        .collect();
+
+    join(segments)
+}
+
+// reassembles parts saved one after another, such as a run of posts in a
+// mailbox, splitting them at their `part N of M` lines
+pub fn reassemble_concatenated(i: &[u8]) -> Reassembly {
+    let mut segments = vec![Segment {
+        index: 0,
+        number: None,
+        total: None,
+        lines: Vec::new(),
+    }];
+
+    for line in lines(i) {
+        if let Some((number, total)) = part_header(line, i.len()) {
+            let current = segments.last_mut().unwrap();
+            // a post often names its part in both the subject and the body
+            if current.number == Some(number) {
+                continue;
+            }
+            if current.number.is_none() && current.lines.is_empty() {
+                current.number = Some(number);
+                current.total = Some(total);
+                continue;
+            }
+            segments.push(Segment {
+                index: segments.len(),
+                number: Some(number),
+                total: Some(total),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        segments.last_mut().unwrap().lines.push(line);
+    }
+
+    join(segments)
+}
+
+struct Segment<'a> {
+    index: usize,
+    number: Option<u32>,
+    total: Option<u32>,
+    lines: Vec<&'a [u8]>,
+}
+
+fn join(mut segments: Vec<Segment>) -> Reassembly {
+    let mut out_of_order = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut highest = 0;
+    let mut seen = HashMap::new();
+    for segment in &segments {
+        if let Some(number) = segment.number {
+            if seen.insert(number, segment.index).is_some() {
+                duplicates.push(number);
+            } else if number < highest {
+                out_of_order.push(number);
+            }
+            highest = highest.max(number);
+        }
+    }
+
+    let total = segments.iter().filter_map(|segment| segment.total).max();
+    let missing = (1..=total.unwrap_or(highest))
+        .filter(|number| !seen.contains_key(number))
+        .take(MAX_MISSING + seen.len())
+        .collect();
+
+    // unnumbered segments stay in front, which is where the text before the
+    // first part header of a concatenated buffer belongs
+    segments.retain(|segment| {
+        segment
+            .number
+            .is_none_or(|number| seen[&number] == segment.index)
+    });
+    segments.sort_by_key(|segment| segment.number);
+
+    let stream = encoded_lines(&segments);
+
+    let mut encoded = Vec::from(BINHEX_FILE_MARKER);
+    encoded.extend_from_slice(b"\r\n\r\n");
+    let mut parts = Vec::with_capacity(segments.len());
+    for (segment, lines) in segments.iter().zip(stream) {
+        // mail headers and the like in front of the first part header
+        if segment.number.is_none() && lines.is_empty() {
+            continue;
+        }
+        for line in &lines {
+            encoded.extend_from_slice(line);
+            encoded.extend_from_slice(b"\r\n");
+        }
+        parts.push(PartInfo {
+            index: segment.index,
+            number: segment.number,
+            total: segment.total,
+            lines: lines.len(),
+        });
+    }
+
+    Reassembly {
+        parts,
+        missing,
+        out_of_order,
+        duplicates,
+        encoded,
+    }
+}
+
+// picks out the encoded lines of each segment: everything from the line
+// starting with `:` up to the line ending with one, skipping signatures,
+// headers and separators in between. a line counts as encoded by where it
+// sits and what it is made of, not by how varied it is, since periodic data
+// such as a solid fill encodes to lines of one repeated character
+fn encoded_lines<'a>(segments: &[Segment<'a>]) -> Vec<Vec<&'a [u8]>> {
+    let width = line_width(
+        segments
+            .iter()
+            .flat_map(|segment| segment.lines.iter().copied()),
+    );
+
+    let mut started = false;
+    let mut finished = false;
+    segments
+        .iter()
+        .map(|segment| {
+            let mut kept = Vec::new();
+            for line in &segment.lines {
+                let line = *line;
+                if finished {
+                    break;
+                }
+                if !started {
+                    if line.first() == Some(&b':') && is_alphabet(&line[1..]) {
+                        started = true;
+                        finished = line.len() > 1 && line.ends_with(b":");
+                        kept.push(line);
+                    }
+                    continue;
+                }
+                if line.ends_with(b":") && is_alphabet(&line[..line.len() - 1]) {
+                    finished = true;
+                    kept.push(line);
+                } else if Some(line.len()) == width && is_alphabet(line) {
+                    kept.push(line);
+                }
+            }
+            kept
+        })
+        .collect()
+}
+
+// the most common length of full encoded lines
+fn line_width<'a, I: Iterator<Item = &'a [u8]>>(lines: I) -> Option<usize> {
+    let mut counts = HashMap::new();
+    for line in lines.filter(|line| is_alphabet(line)) {
+        *counts.entry(line.len()).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(len, count)| (*count, *len))
+        .map(|(len, _)| len)
+}
+
+fn is_alphabet(line: &[u8]) -> bool {
+    line.iter().all(|b| decode_char(*b).is_some())
+}
+
+// splits on any line ending and drops trailing whitespace
+fn lines(i: &[u8]) -> Vec<&[u8]> {
+    i.split(|b| *b == b'\n' || *b == b'\r')
+        .map(|line| {
+            let end = line
+                .iter()
+                .rposition(|b| !b.is_ascii_whitespace())
+                .map_or(0, |end| end + 1);
+            &line[..end]
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// recognizes `part 2 of 5` and `part 2/5` anywhere in a line, and `(2/5)` or
+// `[2/5]` in a subject line. a posting in more parts than `input_len`, the
+// size of the input in bytes, is not believed
+fn part_header(line: &[u8], input_len: usize) -> Option<(u32, u32)> {
+    let line = line.to_ascii_lowercase();
+
+    let mut rest = &line[..];
+    while let Some(start) = find(rest, b"part") {
+        rest = &rest[start + 4..];
+        if let Some(header) = part_numbers(rest, true, input_len) {
+            return Some(header);
+        }
+    }
+
+    if line.starts_with(b"subject:") {
+        let mut rest = &line[..];
+        while let Some(start) = rest.iter().position(|b| *b == b'(' || *b == b'[') {
+            rest = &rest[start + 1..];
+            if let Some(header) = part_numbers(rest, false, input_len) {
+                return Some(header);
+            }
+        }
+    }
+
+    None
+}
+
+fn part_numbers(i: &[u8], allow_of: bool, input_len: usize) -> Option<(u32, u32)> {
+    let i = skip_spaces(i);
+    let (number, i) = parse_number(i)?;
+    let i = skip_spaces(i);
+    let i = match i {
+        [b'/', i @ ..] => i,
+        [b'o', b'f', i @ ..] if allow_of => i,
+        _ => return None,
+    };
+    let (total, _) = parse_number(skip_spaces(i))?;
+
+    let plausible = usize::try_from(total).is_ok_and(|total| total <= input_len);
+    (plausible && (1..=total).contains(&number)).then_some((number, total))
+}
+
+fn parse_number(i: &[u8]) -> Option<(u32, &[u8])> {
+    let len = i.iter().take_while(|b| b.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&i[..len]).ok()?.parse().ok()?;
+    Some((number, &i[len..]))
+}
+
+fn skip_spaces(i: &[u8]) -> &[u8] {
+    let len = i.iter().take_while(|b| **b == b' ' || **b == b'#').count();
+    &i[len..]
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -1,29 +1,149 @@
-use nom::bytes::complete::{tag, take_until};
-use nom::sequence::preceded;
+use nom::bytes::complete::take_until;
 use nom::IResult;
-use nom::Parser;
+
+use crate::decode::decode_char;
+use crate::legacy;
 
 const COLON: &str = ":";
+// BinHex 4.0 writes `(This file must be converted with BinHex 4.0)`; the
+// version is left off so that other spellings of the line are found too
+pub const MARKER: &str = "(This file must be converted with BinHex";
+
+// the generation of BinHex that wrote a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    // BinHex 1.0 and 2.0 `.hex` files, two hex digits a byte
+    Hex,
+    // BinHex 3.0 `.hcx` files, four characters to three bytes
+    CompressedHex,
+    // `.hqx` files
+    BinHex4,
+    // a MacBinary file in BinHex 4.0 encoding
+    BinHex5,
+}
+
+// which generation wrote `i`, if any. a 4.0 or 5.0 marker, or a line of
+// encoded characters, wins over a legacy header, which banners and
+// signatures can look like
+pub fn detect(i: &[u8]) -> Option<Format> {
+    let payload = locate(i);
+    let marker_version = find_marker(i, 0).map(|marker| marker_version(i, marker));
+
+    if let Some(payload) = payload {
+        let legacy_marker = marker_version.is_some_and(is_legacy_version);
+        match payload.detection {
+            Detection::Marker if !legacy_marker => {
+                let binhex5 = marker_version.is_some_and(|version| version.starts_with(b"5."));
+                return Some(if binhex5 {
+                    Format::BinHex5
+                } else {
+                    Format::BinHex4
+                });
+            }
+            Detection::LineStart => return Some(Format::BinHex4),
+            _ => {}
+        }
+    }
+
+    if let Some(header) = legacy::find_header(i) {
+        return Some(if header.compressed {
+            Format::CompressedHex
+        } else {
+            Format::Hex
+        });
+    }
+
+    payload.map(|_| Format::BinHex4)
+}
+
+// the text after the marker, such as `4.0)`
+fn marker_version(i: &[u8], marker: usize) -> &[u8] {
+    i[marker + MARKER.len()..].trim_ascii_start()
+}
+
+// BinHex 1.0 to 3.0, whose files have no 4.0 payload to find
+fn is_legacy_version(version: &[u8]) -> bool {
+    matches!(version, [b'1'..=b'3', b'.', ..])
+}
+
+// how the start of the encoded data was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detection {
+    // the first line starting with `:` after the marker line
+    Marker,
+    // no marker, so the first line that starts with `:` and holds nothing
+    // but encoded characters
+    LineStart,
+    // neither was found, so the first `:` anywhere in the input
+    FirstColon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Payload<'a> {
+    // offset of the opening colon in the input
+    pub offset: usize,
+    // everything between the opening and closing colons
+    pub encoded: &'a [u8],
+    pub detection: Detection,
+}
+
+// finds the encoded data, skipping any preamble such as mail headers
+pub fn locate(i: &[u8]) -> Option<Payload<'_>> {
+    let (offset, detection) = find_marker(i, 0)
+        .and_then(|marker| line_start_colon(i, marker + MARKER.len(), |_| true))
+        .map(|offset| (offset, Detection::Marker))
+        .or_else(|| {
+            line_start_colon(i, 0, is_encoded_line).map(|offset| (offset, Detection::LineStart))
+        })
+        .or_else(|| {
+            i.iter()
+                .position(|b| *b == b':')
+                .map(|offset| (offset, Detection::FirstColon))
+        })?;
+
+    let result: IResult<&[u8], &[u8]> = take_until(COLON)(&i[offset + 1..]);
+    let (_, encoded) = result.ok()?;
+
+    Some(Payload {
+        offset,
+        encoded,
+        detection,
+    })
+}
+
+// offset of the next marker line at or after `from`
+pub fn find_marker(i: &[u8], from: usize) -> Option<usize> {
+    let rest = i.get(from..)?;
+    let result: IResult<&[u8], &[u8]> = take_until(MARKER)(rest);
+    result.ok().map(|(_, before)| from + before.len())
+}
 
-fn first_colon() -> impl FnMut(&[u8]) -> IResult<&[u8], ()> {
-    move |i| preceded(take_until(COLON), tag(COLON)).map(|_| ()).parse(i)
+// the first `:` at or after `from` that begins a line, ignoring indentation,
+// and whose following text passes `accept`
+pub(crate) fn line_start_colon<F: Fn(&[u8]) -> bool>(
+    i: &[u8],
+    from: usize,
+    accept: F,
+) -> Option<usize> {
+    (from..i.len())
+        .find(|&offset| i[offset] == b':' && is_line_start(i, offset) && accept(&i[offset + 1..]))
 }
 
-fn second_colon() -> impl FnMut(&[u8]) -> IResult<&[u8], &[u8]> {
-    move |i| {
-        take_until(COLON)
-            .and(tag(COLON))
-            .map(|(encoded_bin, _colon)| encoded_bin)
-            .parse(i)
+fn is_line_start(i: &[u8], offset: usize) -> bool {
+    match i[..offset].iter().rposition(|b| *b != b' ' && *b != b'\t') {
+        Some(before) => i[before] == b'\r' || i[before] == b'\n',
+        None => true,
     }
 }
 
-pub fn parse(i: &[u8]) -> IResult<&[u8], &[u8]> {
-    let encoded_bin_with_newlines = first_colon()
-        .and(second_colon())
-        .map(|(_first_colon, second_colon)| second_colon)
-        .parse(i)
-        .map(|(_, out)| out)?;
+// whether the rest of the line after an opening colon looks like encoded data
+pub(crate) fn is_encoded_line(rest: &[u8]) -> bool {
+    let line = rest
+        .split(|b| *b == b'\r' || *b == b'\n')
+        .next()
+        .unwrap_or_default()
+        .trim_ascii_end();
+    let line = line.strip_suffix(b":").unwrap_or(line);
 
-    Ok((&[], encoded_bin_with_newlines))
+    !line.is_empty() && line.iter().all(|b| decode_char(*b).is_some())
 }
@@ -0,0 +1,376 @@
+use std::ffi::CString;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read};
+
+use crate::{
+    crc::Crc16,
+    decode::{decode_char, CharDecoder, DecodeMode},
+    error::{CRCVerificationError, DecodeError, ParseError},
+    finder::FinderFlags,
+    fourcc::FourCharCode,
+    parse::{find_marker, is_encoded_line, line_start_colon, locate, MARKER},
+    rle::RleDecoder,
+    sixbit, HQXHeader,
+};
+
+pub struct HexbinReader<R> {
+    input: ExpandedStream<R>,
+    header: HQXHeader,
+    stage: Stage,
+    remaining: u64,
+    crc: Crc16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Data,
+    Resource,
+    Done,
+}
+
+impl<R: Read> HexbinReader<R> {
+    pub fn new(reader: R) -> Result<HexbinReader<R>, DecodeError> {
+        HexbinReader::with_mode(reader, DecodeMode::default())
+    }
+
+    pub fn with_mode(reader: R, mode: DecodeMode) -> Result<HexbinReader<R>, DecodeError> {
+        let mut input = ExpandedStream::new(reader, mode);
+        input.skip_preamble()?;
+
+        let header = read_header(&mut input)?;
+        let remaining = header.data_len as u64;
+
+        Ok(HexbinReader {
+            input,
+            header,
+            stage: Stage::Data,
+            remaining,
+            crc: Crc16::new(),
+        })
+    }
+
+    pub fn header(&self) -> &HQXHeader {
+        &self.header
+    }
+
+    pub fn data_fork(&mut self) -> ForkReader<'_, R> {
+        ForkReader {
+            reader: self,
+            stage: Stage::Data,
+        }
+    }
+
+    // skips whatever is left of the data fork, verifying its crc
+    pub fn resource_fork(&mut self) -> Result<ForkReader<'_, R>, DecodeError> {
+        io::copy(&mut self.data_fork(), &mut io::sink())?;
+
+        Ok(ForkReader {
+            reader: self,
+            stage: Stage::Resource,
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.input.input.into_inner()
+    }
+
+    fn read_fork(&mut self, stage: Stage, buf: &mut [u8]) -> Result<usize, DecodeError> {
+        if self.stage != stage {
+            return Ok(0);
+        }
+
+        if self.remaining == 0 {
+            self.finish_fork()?;
+            return Ok(0);
+        }
+
+        let len = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let read = self.input.read(&mut buf[..len])?;
+        if read == 0 {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+
+        self.crc.update(&buf[..read]);
+        self.remaining -= read as u64;
+
+        if self.remaining == 0 {
+            self.finish_fork()?;
+        }
+
+        Ok(read)
+    }
+
+    fn finish_fork(&mut self) -> Result<(), DecodeError> {
+        let mut crc_bytes = [0; 2];
+        self.input.read_exact(&mut crc_bytes)?;
+        let expected_crc = u16::from_be_bytes(crc_bytes);
+        let crc = self.crc.finish();
+
+        let (fork_len, err, next) = match self.stage {
+            Stage::Data => (
+                self.header.data_len,
+                CRCVerificationError::Data,
+                Stage::Resource,
+            ),
+            Stage::Resource => (
+                self.header.resource_len,
+                CRCVerificationError::Resource,
+                Stage::Done,
+            ),
+            Stage::Done => return Ok(()),
+        };
+
+        self.stage = next;
+        self.remaining = match next {
+            Stage::Resource => self.header.resource_len as u64,
+            _ => 0,
+        };
+        self.crc = Crc16::new();
+
+        // empty forks are not verified, matching `verify`
+        if fork_len > 0 && crc != expected_crc {
+            return Err(DecodeError::CRCVerificationError(err));
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ForkReader<'a, R> {
+    reader: &'a mut HexbinReader<R>,
+    stage: Stage,
+}
+
+impl<'a, R: Read> ForkReader<'a, R> {
+    pub fn len(&self) -> u32 {
+        match self.stage {
+            Stage::Data => self.reader.header.data_len,
+            _ => self.reader.header.resource_len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a, R: Read> Read for ForkReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        Ok(self.reader.read_fork(self.stage, buf)?)
+    }
+}
+
+fn read_header<R: Read>(input: &mut ExpandedStream<R>) -> Result<HQXHeader, DecodeError> {
+    let mut name_len = [0; 1];
+    input.read_exact(&mut name_len)?;
+    let mut crc = Crc16::new();
+    crc.update(&name_len);
+
+    // add one to include null terminator
+    let mut name = vec![0; name_len[0] as usize + 1];
+    input.read_exact(&mut name)?;
+    crc.update(&name);
+    let bad_name = || ParseError::BadName { offset: 1 };
+    if name.pop() != Some(0) {
+        return Err(bad_name().into());
+    }
+    let name = CString::new(name).map_err(|_| bad_name())?;
+
+    let mut rest = [0; 4 + 4 + 2 + 4 + 4];
+    input.read_exact(&mut rest)?;
+    crc.update(&rest);
+
+    let mut hc_bytes = [0; 2];
+    input.read_exact(&mut hc_bytes)?;
+    let hc = u16::from_be_bytes(hc_bytes);
+
+    if crc.finish() != hc {
+        return Err(DecodeError::CRCVerificationError(
+            CRCVerificationError::Header,
+        ));
+    }
+
+    let field = |start: usize| <[u8; 4]>::try_from(&rest[start..start + 4]).unwrap();
+
+    Ok(HQXHeader {
+        name,
+        file_type: FourCharCode(field(0)),
+        author: FourCharCode(field(4)),
+        flags: FinderFlags::from_bytes([rest[8], rest[9]]),
+        data_len: u32::from_be_bytes(field(10)),
+        resource_len: u32::from_be_bytes(field(14)),
+        hc,
+    })
+}
+
+// yields the decoded, run-length expanded bytes of the encoded stream,
+// decoding a buffer of input at a time
+struct ExpandedStream<R> {
+    input: BufReader<R>,
+    // the end of the preamble, read before the start of the data was known
+    pending: Vec<u8>,
+    // positioned at the start of the input not yet decoded
+    chars: CharDecoder,
+    // characters of a partial group, decoded with the next buffer
+    carry: Vec<u8>,
+    rle: RleDecoder,
+    expanded: Vec<u8>,
+    expanded_pos: usize,
+    ended: bool,
+}
+
+impl<R: Read> ExpandedStream<R> {
+    fn new(reader: R, mode: DecodeMode) -> ExpandedStream<R> {
+        ExpandedStream {
+            input: BufReader::new(reader),
+            pending: Vec::new(),
+            chars: CharDecoder::new(mode),
+            carry: Vec::new(),
+            rle: RleDecoder::new(mode),
+            expanded: Vec::new(),
+            expanded_pos: 0,
+            ended: false,
+        }
+    }
+
+    // skips to the opening colon the way `locate` finds it: after the
+    // marker if there is one, otherwise on the first line of nothing but
+    // encoded characters, otherwise anywhere, which needs all of the input.
+    // a marker after such a line cannot be seen coming, so the line wins
+    fn skip_preamble(&mut self) -> Result<(), DecodeError> {
+        let mut preamble = Vec::new();
+        let mut after_marker = None;
+        let start = loop {
+            let line_start = preamble.len();
+            if !self.read_line(&mut preamble)? {
+                break locate(&preamble)
+                    .map(|payload| payload.offset)
+                    .ok_or(DecodeError::BadFormat)?;
+            }
+
+            if after_marker.is_none() {
+                after_marker =
+                    find_marker(&preamble, line_start).map(|marker| marker + MARKER.len());
+            }
+            let colon = match after_marker {
+                Some(from) => line_start_colon(&preamble, from.max(line_start), |_| true),
+                None => line_start_colon(&preamble, line_start, is_encoded_line),
+            };
+            if let Some(colon) = colon {
+                break colon;
+            }
+        };
+
+        self.chars.advance_over(&preamble[..start + 1]);
+        self.pending = preamble.split_off(start + 1);
+        Ok(())
+    }
+
+    // appends the next line, with its line break, returning false at the end
+    fn read_line(&mut self, line: &mut Vec<u8>) -> Result<bool, DecodeError> {
+        let mut read = false;
+        loop {
+            let buf = self.input.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(read);
+            }
+            read = true;
+
+            match buf.iter().position(|b| *b == b'\r' || *b == b'\n') {
+                Some(end) => {
+                    line.extend_from_slice(&buf[..end + 1]);
+                    self.input.consume(end + 1);
+                    return Ok(true);
+                }
+                None => {
+                    let len = buf.len();
+                    line.extend_from_slice(buf);
+                    self.input.consume(len);
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, DecodeError> {
+        while self.expanded_pos == self.expanded.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+
+        let expanded = &self.expanded[self.expanded_pos..];
+        let len = buf.len().min(expanded.len());
+        buf[..len].copy_from_slice(&expanded[..len]);
+        self.expanded_pos += len;
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), DecodeError> {
+        while !buf.is_empty() {
+            let read = self.read(buf)?;
+            if read == 0 {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+            buf = &mut buf[read..];
+        }
+        Ok(())
+    }
+
+    // decodes the next buffer of input up to the closing colon, returning
+    // false once there is nothing left
+    fn fill(&mut self) -> Result<bool, DecodeError> {
+        if self.ended {
+            return Ok(false);
+        }
+        self.expanded.clear();
+        self.expanded_pos = 0;
+
+        let mut encoded = std::mem::take(&mut self.pending);
+        if encoded.is_empty() {
+            let buf = self.input.fill_buf()?;
+            let end = buf.iter().position(|b| *b == b':');
+            encoded.extend_from_slice(&buf[..end.unwrap_or(buf.len())]);
+            let consumed = end.map_or(buf.len(), |end| end + 1);
+            // the end of the input ends the data too, as with `hexbin`
+            self.ended = end.is_some() || buf.is_empty();
+            self.input.consume(consumed);
+        } else if let Some(end) = encoded.iter().position(|b| *b == b':') {
+            encoded.truncate(end);
+            self.ended = true;
+        }
+
+        let mut chars = std::mem::take(&mut self.carry);
+        chars.extend_from_slice(&sixbit::strip(&encoded, self.chars.mode()));
+        let whole = if self.ended {
+            chars.len()
+        } else {
+            chars.len() / 4 * 4
+        };
+        let carried_ok = chars[whole..].iter().all(|b| decode_char(*b).is_some());
+        let compressed = match sixbit::decode(&chars[..whole]) {
+            Ok(compressed) if carried_ok => compressed,
+            _ => return Err(self.illegal_character(&encoded)),
+        };
+        self.carry = chars[whole..].to_vec();
+        self.chars.advance_over(&encoded);
+
+        self.rle.push_all(&compressed, &mut self.expanded)?;
+        if self.ended {
+            self.rle.finish()?;
+        }
+        Ok(true)
+    }
+
+    // goes over the input again to find the line and column
+    fn illegal_character(&self, encoded: &[u8]) -> DecodeError {
+        let mut chars = self.chars.clone();
+        encoded
+            .iter()
+            .find_map(|b6| chars.push(*b6).err())
+            .unwrap_or(DecodeError::BadFormat)
+    }
+}
@@ -0,0 +1,288 @@
+use std::borrow::Cow;
+
+use crate::{error::ResourceError, fourcc::FourCharCode, HQXRef};
+
+const HEADER_LEN: usize = 16;
+// the header is followed by 112 bytes reserved for the system and 128 for the
+// application before the resource data
+const DATA_START: usize = 256;
+const MAP_HEADER_LEN: usize = HEADER_LEN + 4 + 2 + 2 + 2 + 2;
+const TYPE_ENTRY_LEN: usize = 4 + 2 + 2;
+const REF_ENTRY_LEN: usize = 2 + 2 + 1 + 3 + 4;
+const NO_NAME: u16 = 0xFFFF;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceFork<'a> {
+    pub attributes: u16,
+    pub resources: Vec<Resource<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource<'a> {
+    pub res_type: FourCharCode,
+    pub id: i16,
+    pub name: Option<Cow<'a, [u8]>>,
+    pub attributes: u8,
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> ResourceFork<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<ResourceFork<'a>, ResourceError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(ResourceError::TruncatedHeader);
+        }
+
+        let data_offset = be_u32(bytes, 0)? as usize;
+        let map_offset = be_u32(bytes, 4)? as usize;
+
+        let attributes = be_u16(bytes, map_offset + 22)?;
+        let type_list = map_offset + be_u16(bytes, map_offset + 24)? as usize;
+        let name_list = map_offset + be_u16(bytes, map_offset + 26)? as usize;
+
+        // counts are stored minus one, so an empty list is 0xFFFF
+        let type_count = be_u16(bytes, type_list)?.wrapping_add(1) as usize;
+
+        // type entries may share a ref list, so the counts alone could ask
+        // for billions of resources; there can be no more than the map holds
+        let map_len = (be_u32(bytes, 12)? as usize).min(bytes.len().saturating_sub(map_offset));
+        let mut ref_total = 0;
+        for i in 0..type_count {
+            ref_total += be_u16(bytes, type_list + 2 + i * TYPE_ENTRY_LEN + 4)? as usize + 1;
+        }
+        if ref_total > map_len / REF_ENTRY_LEN {
+            return Err(ResourceError::TooManyResources);
+        }
+
+        let mut resources = Vec::with_capacity(ref_total);
+        for i in 0..type_count {
+            let entry = type_list + 2 + i * TYPE_ENTRY_LEN;
+            let res_type = FourCharCode(<[u8; 4]>::try_from(slice(bytes, entry, 4)?).unwrap());
+            let ref_count = be_u16(bytes, entry + 4)? as usize + 1;
+            let ref_list = type_list + be_u16(bytes, entry + 6)? as usize;
+
+            for j in 0..ref_count {
+                let entry = ref_list + j * REF_ENTRY_LEN;
+                let id = be_u16(bytes, entry)? as i16;
+                let name_offset = be_u16(bytes, entry + 2)?;
+                let attributes = *slice(bytes, entry + 4, 1)?.first().unwrap();
+                let res_data_offset = be_u32(bytes, entry + 4)? as usize & 0x00FF_FFFF;
+
+                let name = match name_offset {
+                    NO_NAME => None,
+                    name_offset => {
+                        let name = name_list + name_offset as usize;
+                        let name_len = *slice(bytes, name, 1)?.first().unwrap() as usize;
+                        Some(Cow::Borrowed(slice(bytes, name + 1, name_len)?))
+                    }
+                };
+
+                let res_data = data_offset + res_data_offset;
+                let res_data_len = be_u32(bytes, res_data)? as usize;
+                let data = Cow::Borrowed(slice(bytes, res_data + 4, res_data_len)?);
+
+                resources.push(Resource {
+                    res_type,
+                    id,
+                    name,
+                    attributes,
+                    data,
+                });
+            }
+        }
+
+        Ok(ResourceFork {
+            attributes,
+            resources,
+        })
+    }
+
+    pub fn get(&self, res_type: FourCharCode, id: i16) -> Option<&Resource<'a>> {
+        self.resources
+            .iter()
+            .find(|resource| resource.res_type == res_type && resource.id == id)
+    }
+
+    pub fn get_mut(&mut self, res_type: FourCharCode, id: i16) -> Option<&mut Resource<'a>> {
+        self.resources
+            .iter_mut()
+            .find(|resource| resource.res_type == res_type && resource.id == id)
+    }
+
+    pub fn of_type(&self, res_type: FourCharCode) -> impl Iterator<Item = &Resource<'a>> {
+        self.resources
+            .iter()
+            .filter(move |resource| resource.res_type == res_type)
+    }
+
+    // resource types in the order they first appear
+    pub fn types(&self) -> Vec<FourCharCode> {
+        let mut types = Vec::new();
+        for resource in self.resources.iter() {
+            if !types.contains(&resource.res_type) {
+                types.push(resource.res_type);
+            }
+        }
+        types
+    }
+
+    pub fn into_owned(self) -> ResourceFork<'static> {
+        ResourceFork {
+            attributes: self.attributes,
+            resources: self
+                .resources
+                .into_iter()
+                .map(|resource| Resource {
+                    res_type: resource.res_type,
+                    id: resource.id,
+                    name: resource.name.map(|name| Cow::Owned(name.into_owned())),
+                    attributes: resource.attributes,
+                    data: Cow::Owned(resource.data.into_owned()),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ResourceError> {
+        let types = self.types();
+        let type_count: u16 = types
+            .len()
+            .checked_sub(1)
+            .map_or(Ok(0xFFFF), |count| count.try_into())
+            .map_err(|_| ResourceError::TooManyResources)?;
+
+        let mut data = Vec::new();
+        let mut type_entries = Vec::new();
+        let mut ref_entries = Vec::new();
+        let mut names = Vec::new();
+
+        let ref_lists_start = 2 + types.len() * TYPE_ENTRY_LEN;
+
+        for res_type in types.iter() {
+            let ref_list = ref_lists_start + ref_entries.len();
+            let mut ref_count = 0usize;
+
+            for resource in self.of_type(*res_type) {
+                let name_offset = match resource.name.as_ref() {
+                    Some(name) => {
+                        let name_offset: u16 = names
+                            .len()
+                            .try_into()
+                            .ok()
+                            .filter(|offset| *offset != NO_NAME)
+                            .ok_or(ResourceError::MapTooLarge)?;
+                        let name_len: u8 = name
+                            .len()
+                            .try_into()
+                            .map_err(|_| ResourceError::NameTooLong)?;
+                        names.push(name_len);
+                        names.extend_from_slice(name);
+                        name_offset
+                    }
+                    None => NO_NAME,
+                };
+
+                let res_data_offset = data.len();
+                if res_data_offset > 0x00FF_FFFF {
+                    return Err(ResourceError::DataTooLarge);
+                }
+                let res_data_len: u32 = resource
+                    .data
+                    .len()
+                    .try_into()
+                    .map_err(|_| ResourceError::DataTooLarge)?;
+                data.extend_from_slice(&res_data_len.to_be_bytes());
+                data.extend_from_slice(&resource.data);
+
+                ref_entries.extend_from_slice(&resource.id.to_be_bytes());
+                ref_entries.extend_from_slice(&name_offset.to_be_bytes());
+                ref_entries.push(resource.attributes);
+                ref_entries.extend_from_slice(&(res_data_offset as u32).to_be_bytes()[1..]);
+                ref_entries.extend_from_slice(&[0; 4]); // handle
+                ref_count += 1;
+            }
+
+            type_entries.extend_from_slice(res_type.as_bytes());
+            let ref_count: u16 = (ref_count - 1)
+                .try_into()
+                .map_err(|_| ResourceError::TooManyResources)?;
+            type_entries.extend_from_slice(&ref_count.to_be_bytes());
+            let ref_list: u16 = ref_list
+                .try_into()
+                .map_err(|_| ResourceError::MapTooLarge)?;
+            type_entries.extend_from_slice(&ref_list.to_be_bytes());
+        }
+
+        let name_list_offset: u16 = (MAP_HEADER_LEN + ref_lists_start + ref_entries.len())
+            .try_into()
+            .map_err(|_| ResourceError::MapTooLarge)?;
+        let map_len = name_list_offset as usize + names.len();
+        let map_offset = DATA_START + data.len();
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&(DATA_START as u32).to_be_bytes());
+        header.extend_from_slice(&(map_offset as u32).to_be_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        header.extend_from_slice(&(map_len as u32).to_be_bytes());
+
+        let mut bytes = Vec::with_capacity(map_offset + map_len);
+        bytes.extend_from_slice(&header);
+        bytes.resize(DATA_START, 0);
+        bytes.extend_from_slice(&data);
+
+        // map header: a copy of the fork header, then the handle to the next
+        // map and file reference number, which are only meaningful in memory
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&[0; 4 + 2]);
+        bytes.extend_from_slice(&self.attributes.to_be_bytes());
+        bytes.extend_from_slice(&(MAP_HEADER_LEN as u16).to_be_bytes());
+        bytes.extend_from_slice(&name_list_offset.to_be_bytes());
+
+        bytes.extend_from_slice(&type_count.to_be_bytes());
+        bytes.extend_from_slice(&type_entries);
+        bytes.extend_from_slice(&ref_entries);
+        bytes.extend_from_slice(&names);
+
+        Ok(bytes)
+    }
+}
+
+impl<'a> Resource<'a> {
+    pub fn new(res_type: FourCharCode, id: i16, data: impl Into<Cow<'a, [u8]>>) -> Resource<'a> {
+        Resource {
+            res_type,
+            id,
+            name: None,
+            attributes: 0,
+            data: data.into(),
+        }
+    }
+}
+
+impl<'a> HQXRef<'a> {
+    // an absent resource fork parses as an empty one
+    pub fn resources(&self) -> Result<ResourceFork<'a>, ResourceError> {
+        match self.resource_fork.as_ref() {
+            Some(fork) => ResourceFork::parse(fork.data),
+            None => Ok(ResourceFork::default()),
+        }
+    }
+}
+
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], ResourceError> {
+    bytes
+        .get(offset..)
+        .and_then(|rest| rest.get(..len))
+        .ok_or(ResourceError::OutOfBounds { offset })
+}
+
+fn be_u16(bytes: &[u8], offset: usize) -> Result<u16, ResourceError> {
+    Ok(u16::from_be_bytes(
+        <[u8; 2]>::try_from(slice(bytes, offset, 2)?).unwrap(),
+    ))
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> Result<u32, ResourceError> {
+    Ok(u32::from_be_bytes(
+        <[u8; 4]>::try_from(slice(bytes, offset, 4)?).unwrap(),
+    ))
+}
@@ -0,0 +1,154 @@
+use crate::{decode::DecodeMode, error::DecodeError};
+
+pub const RLE_MARKER_BYTE: u8 = 0x90;
+
+// longest run a single marker can express, counting the literal byte itself
+const MAX_RUN: usize = 255;
+
+#[derive(Debug, Default)]
+pub struct RleEncoder {
+    run: Option<(u8, usize)>,
+}
+
+impl RleEncoder {
+    pub fn new() -> RleEncoder {
+        RleEncoder::default()
+    }
+
+    pub fn push(&mut self, byte: u8, out: &mut Vec<u8>) {
+        match self.run.as_mut() {
+            Some((last, count)) if *last == byte && *count < MAX_RUN => {
+                *count += 1;
+            }
+            _ => {
+                self.flush(out);
+                self.run = Some((byte, 1));
+            }
+        }
+    }
+
+    pub fn push_all(&mut self, bytes: &[u8], out: &mut Vec<u8>) {
+        for byte in bytes.iter().copied() {
+            self.push(byte, out);
+        }
+    }
+
+    pub fn finish(&mut self, out: &mut Vec<u8>) {
+        self.flush(out);
+    }
+
+    fn flush(&mut self, out: &mut Vec<u8>) {
+        let (byte, count) = match self.run.take() {
+            Some(run) => run,
+            None => return,
+        };
+
+        push_literal(byte, out);
+
+        // a marker costs two bytes, so it only pays off for runs of three or
+        // more, or for runs of the (escaped, two byte) marker byte itself
+        if count > 2 || (byte == RLE_MARKER_BYTE && count > 1) {
+            out.push(RLE_MARKER_BYTE);
+            out.push(count as u8);
+        } else {
+            for _ in 1..count {
+                push_literal(byte, out);
+            }
+        }
+    }
+}
+
+fn push_literal(byte: u8, out: &mut Vec<u8>) {
+    out.push(byte);
+    if byte == RLE_MARKER_BYTE {
+        // a literal marker byte is escaped as 0x90 0x00
+        out.push(0x00);
+    }
+}
+
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut encoder = RleEncoder::new();
+    encoder.push_all(bytes, &mut out);
+    encoder.finish(&mut out);
+    out
+}
+
+#[derive(Debug)]
+pub struct RleDecoder {
+    mode: DecodeMode,
+    has_marker: bool,
+    last_byte: Option<u8>,
+}
+
+impl RleDecoder {
+    pub fn new(mode: DecodeMode) -> RleDecoder {
+        RleDecoder {
+            mode,
+            has_marker: false,
+            last_byte: None,
+        }
+    }
+
+    // returns the byte to output and how many times to output it
+    pub fn push(&mut self, data: u8) -> Result<(u8, usize), DecodeError> {
+        if !self.has_marker {
+            if data == RLE_MARKER_BYTE {
+                self.has_marker = true;
+                Ok((data, 0))
+            } else {
+                self.last_byte = Some(data);
+                Ok((data, 1))
+            }
+        } else {
+            self.has_marker = false;
+            if data == 0x00 {
+                self.last_byte = Some(RLE_MARKER_BYTE);
+                Ok((RLE_MARKER_BYTE, 1))
+            } else {
+                let last_byte = match self.last_byte {
+                    Some(last_byte) => last_byte,
+                    // a run with nothing before it to repeat
+                    None if self.mode == DecodeMode::Strict => {
+                        return Err(DecodeError::BadRunLengthEncoding)
+                    }
+                    None => 0,
+                };
+                // the count includes the byte that was already output
+                Ok((last_byte, data as usize - 1))
+            }
+        }
+    }
+
+    // decodes a whole buffer, copying the bytes between markers as they are
+    pub fn push_all(&mut self, mut data: &[u8], out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        while let Some(first) = data.first().copied() {
+            if !self.has_marker {
+                let literal = data
+                    .iter()
+                    .position(|b| *b == RLE_MARKER_BYTE)
+                    .unwrap_or(data.len());
+                if literal > 0 {
+                    out.extend_from_slice(&data[..literal]);
+                    self.last_byte = Some(data[literal - 1]);
+                    data = &data[literal..];
+                    continue;
+                }
+            }
+
+            let (byte, count) = self.push(first)?;
+            out.extend(std::iter::repeat_n(byte, count));
+            data = &data[1..];
+        }
+        Ok(())
+    }
+
+    // checks that the stream did not end on a marker without its count
+    pub fn finish(&self) -> Result<(), DecodeError> {
+        if self.has_marker && self.mode == DecodeMode::Strict {
+            Err(DecodeError::BadRunLengthEncoding)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    applefile::{apple_double_path, AppleFile, FINDER_INFO},
+    builder::HqxBuilder,
+    finder::FinderFlags,
+    fourcc::{self, FourCharCode},
+    macroman, HQX,
+};
+
+// where hosts keep the metadata of a classic Mac OS file: as `._` files
+// next to it (macOS, Netatalk 3), in a `.AppleDouble` directory (Netatalk 2)
+// or in extended attributes (macOS, Samba)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidecarKind {
+    AppleDouble,
+    Netatalk,
+    ExtendedAttributes,
+}
+
+// the parts of a classic Mac OS file that a host file does not hold
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sidecar {
+    pub real_name: Option<Vec<u8>>,
+    // type, creator and Finder flags, then the rest of the 32 bytes the
+    // Finder keeps
+    pub finder_info: Option<Vec<u8>>,
+    pub resource_fork: Option<Vec<u8>>,
+}
+
+// the extended attributes macOS uses, which Linux keeps in the `user`
+// namespace
+pub(crate) const FINDER_INFO_ATTRIBUTES: [&str; 2] =
+    ["com.apple.FinderInfo", "user.com.apple.FinderInfo"];
+pub(crate) const RESOURCE_FORK_ATTRIBUTES: [&str; 2] =
+    ["com.apple.ResourceFork", "user.com.apple.ResourceFork"];
+
+impl Sidecar {
+    // reads the sidecar of one kind, if `path` has one
+    pub fn read<P: AsRef<Path>>(path: P, kind: SidecarKind) -> io::Result<Option<Sidecar>> {
+        let path = path.as_ref();
+        match kind {
+            SidecarKind::AppleDouble => read_apple_double(&apple_double_path(path)?),
+            SidecarKind::Netatalk => read_apple_double(&netatalk_path(path)?),
+            SidecarKind::ExtendedAttributes => Ok(read_attributes(path)),
+        }
+    }
+
+    // merges every sidecar of `path`, preferring a `._` file, then a
+    // `.AppleDouble` directory, then extended attributes
+    pub fn read_any<P: AsRef<Path>>(path: P) -> io::Result<Sidecar> {
+        let path = path.as_ref();
+        let mut merged = Sidecar::default();
+        for kind in [
+            SidecarKind::AppleDouble,
+            SidecarKind::Netatalk,
+            SidecarKind::ExtendedAttributes,
+        ] {
+            if let Some(sidecar) = Sidecar::read(path, kind)? {
+                merged.real_name = merged.real_name.or(sidecar.real_name);
+                merged.finder_info = merged.finder_info.or(sidecar.finder_info);
+                merged.resource_fork = merged.resource_fork.or(sidecar.resource_fork);
+            }
+        }
+        Ok(merged)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Sidecar::default()
+    }
+
+    // type, creator and Finder flags
+    pub fn file_info(&self) -> Option<(FourCharCode, FourCharCode, FinderFlags)> {
+        let info = self.finder_info.as_ref()?.get(..10)?;
+        Some((
+            FourCharCode(info[0..4].try_into().unwrap()),
+            FourCharCode(info[4..8].try_into().unwrap()),
+            FinderFlags::from_bytes(info[8..10].try_into().unwrap()),
+        ))
+    }
+}
+
+// `dir/.AppleDouble/name` for `dir/name`
+pub fn netatalk_path<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path has no file name"))?;
+
+    Ok(path.with_file_name(".AppleDouble").join(name))
+}
+
+fn read_apple_double(path: &Path) -> io::Result<Option<Sidecar>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let apple_double =
+        AppleFile::parse(&bytes).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+
+    Ok(Some(Sidecar {
+        real_name: apple_double.real_name().map(<[u8]>::to_vec),
+        finder_info: apple_double.entry(FINDER_INFO).map(<[u8]>::to_vec),
+        resource_fork: apple_double.resource_fork().map(<[u8]>::to_vec),
+    }))
+}
+
+// attributes that cannot be read, such as on file systems without them, are
+// treated as missing
+#[cfg(unix)]
+fn read_attributes(path: &Path) -> Option<Sidecar> {
+    let attribute = |names: [&str; 2]| {
+        names
+            .into_iter()
+            .find_map(|name| xattr::get(path, name).ok().flatten())
+    };
+
+    let sidecar = Sidecar {
+        real_name: None,
+        finder_info: attribute(FINDER_INFO_ATTRIBUTES),
+        resource_fork: attribute(RESOURCE_FORK_ATTRIBUTES),
+    };
+    (!sidecar.is_empty()).then_some(sidecar)
+}
+
+#[cfg(not(unix))]
+fn read_attributes(_: &Path) -> Option<Sidecar> {
+    None
+}
+
+// a classic Mac OS name for a host file: `:` becomes `/`, as the Finder
+// shows it, characters outside Mac OS Roman become `_`, and the name is cut
+// to the 63 characters HFS allows, keeping the extension where it can
+fn mac_name(path: &Path) -> Vec<u8> {
+    const MAX_HFS_NAME_LEN: usize = 63;
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let mut bytes: Vec<u8> = name
+        .chars()
+        .map(|c| match c {
+            ':' => b'/',
+            '\0' => b'_',
+            c => macroman::encode_char(c).unwrap_or(b'_'),
+        })
+        .collect();
+
+    if bytes.len() > MAX_HFS_NAME_LEN {
+        let extension = bytes
+            .iter()
+            .rposition(|b| *b == b'.')
+            .map(|dot| bytes.len() - dot)
+            .filter(|len| *len < MAX_HFS_NAME_LEN / 2)
+            .unwrap_or(0);
+        bytes.drain(MAX_HFS_NAME_LEN - extension..bytes.len() - extension);
+    }
+    if bytes.is_empty() {
+        bytes.push(b'_');
+    }
+
+    bytes
+}
+
+impl HQX {
+    // reads a host file along with whatever sidecar holds its resource fork
+    // and Finder info; without Finder info, the type and creator are guessed
+    // from the extension. the inverse of `HQXRef::extract`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<HQX> {
+        let path = path.as_ref();
+        from_sidecar(path, Sidecar::read_any(path)?)
+    }
+}
+
+// builds a file from the data fork at `path` and the rest from `sidecar`
+pub(crate) fn from_sidecar(path: &Path, sidecar: Sidecar) -> io::Result<HQX> {
+    let data = fs::read(path)?;
+
+    let (file_type, creator, flags) = sidecar.file_info().unwrap_or_else(|| {
+        let kind = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(fourcc::lookup_extension);
+        match kind {
+            Some(kind) => (
+                kind.file_type,
+                kind.creator.unwrap_or_default(),
+                FinderFlags::default(),
+            ),
+            None => Default::default(),
+        }
+    });
+
+    let builder = HqxBuilder::new()
+        .file_type(file_type)
+        .creator(creator)
+        .finder_flags(flags)
+        .data_fork(data)
+        .resource_fork(sidecar.resource_fork.unwrap_or_default());
+    let builder = match sidecar.real_name {
+        // already a Mac name, which HFS+ allowed to be longer
+        Some(name) => builder.mac_roman_name(name).hfs_name_limit(false),
+        None => builder.mac_roman_name(mac_name(path)),
+    };
+
+    Ok(builder.build()?)
+}
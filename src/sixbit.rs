@@ -0,0 +1,446 @@
+use crate::decode::DecodeMode;
+
+// the BinHex 4.0 alphabet, in order of the 6-bit value each character encodes
+pub(crate) const ALPHABET: &[u8; 64] =
+    b"!\"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr";
+
+pub(crate) const INVALID: u8 = 0xFF;
+
+// some old Mac files are padded with 0xFF, the value `EOF` had on the Mac
+const EOF: u8 = 0xFF;
+
+// the 6-bit value of each character, or `INVALID`
+pub(crate) static DECODINGS: [u8; 256] = make_decodings();
+
+const fn make_decodings() -> [u8; 256] {
+    let mut decodings = [INVALID; 256];
+    let mut value = 0;
+    while value < 64 {
+        decodings[ALPHABET[value] as usize] = value as u8;
+        value += 1;
+    }
+    decodings
+}
+
+// whether the decoder passes over `b` between encoded characters
+pub(crate) fn is_skipped(b: u8, mode: DecodeMode) -> bool {
+    match b {
+        b'\r' | b'\n' => true,
+        b' ' | b'\t' | 0x0B | 0x0C | EOF => mode == DecodeMode::Lenient,
+        _ => false,
+    }
+}
+
+// the encoded characters without the line breaks, and in lenient mode the
+// other bytes, that the decoder passes over
+pub fn strip(encoded: &[u8], mode: DecodeMode) -> Vec<u8> {
+    let mut chars = Vec::with_capacity(encoded.len());
+    for line in encoded.split(|b| is_skipped(*b, mode)) {
+        chars.extend_from_slice(line);
+    }
+    chars
+}
+
+// decodes characters four to three bytes at a time, using simd where the
+// `simd` feature and the cpu allow it; a trailing partial group decodes to
+// the whole bytes it holds. fails with the index of the first character
+// outside the alphabet
+pub fn decode(chars: &[u8]) -> Result<Vec<u8>, usize> {
+    let mut bytes = Vec::with_capacity(decoded_len(chars.len()));
+    let decoded = simd::decode_blocks(chars, &mut bytes)?;
+    decode_from(chars, decoded, &mut bytes)?;
+    Ok(bytes)
+}
+
+// the same without simd, as a fallback and a reference
+pub fn decode_scalar(chars: &[u8]) -> Result<Vec<u8>, usize> {
+    let mut bytes = Vec::with_capacity(decoded_len(chars.len()));
+    decode_from(chars, 0, &mut bytes)?;
+    Ok(bytes)
+}
+
+// encodes bytes three to four characters at a time, with no line breaks; a
+// trailing partial group is padded with zero bits
+pub fn encode(bytes: &[u8]) -> Vec<u8> {
+    let mut chars = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+    let encoded = simd::encode_blocks(bytes, &mut chars);
+    encode_from(bytes, encoded, &mut chars);
+    chars
+}
+
+pub fn encode_scalar(bytes: &[u8]) -> Vec<u8> {
+    let mut chars = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+    encode_from(bytes, 0, &mut chars);
+    chars
+}
+
+fn decoded_len(char_count: usize) -> usize {
+    char_count / 4 * 3 + (char_count % 4).saturating_sub(1)
+}
+
+fn first_invalid(chars: &[u8], from: usize) -> usize {
+    from + chars[from..]
+        .iter()
+        .position(|b| DECODINGS[*b as usize] == INVALID)
+        .unwrap_or_default()
+}
+
+fn decode_from(chars: &[u8], start: usize, bytes: &mut Vec<u8>) -> Result<(), usize> {
+    let mut groups = chars[start..].chunks_exact(4);
+    let mut at = start;
+    for group in &mut groups {
+        let values = [0, 1, 2, 3].map(|k| DECODINGS[group[k] as usize]);
+        // values fit in 6 bits, so only `INVALID` sets the top two
+        if (values[0] | values[1] | values[2] | values[3]) & 0xC0 != 0 {
+            return Err(first_invalid(chars, at));
+        }
+        let group = (values[0] as u32) << 18
+            | (values[1] as u32) << 12
+            | (values[2] as u32) << 6
+            | values[3] as u32;
+        bytes.extend_from_slice(&group.to_be_bytes()[1..]);
+        at += 4;
+    }
+
+    let rest = groups.remainder();
+    let mut group = 0u32;
+    for (k, b) in rest.iter().enumerate() {
+        let value = DECODINGS[*b as usize];
+        if value == INVALID {
+            return Err(at + k);
+        }
+        group |= (value as u32) << (18 - 6 * k);
+    }
+    bytes.extend_from_slice(&group.to_be_bytes()[1..rest.len().max(1)]);
+
+    Ok(())
+}
+
+fn encode_from(bytes: &[u8], start: usize, chars: &mut Vec<u8>) {
+    let mut groups = bytes[start..].chunks_exact(3);
+    for group in &mut groups {
+        let group = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        chars.extend_from_slice(
+            &[18, 12, 6, 0].map(|shift| ALPHABET[(group >> shift) as usize & 0x3F]),
+        );
+    }
+
+    let rest = groups.remainder();
+    if !rest.is_empty() {
+        let mut group = [0; 4];
+        group[1..1 + rest.len()].copy_from_slice(rest);
+        let group = u32::from_be_bytes(group);
+        for shift in [18, 12, 6].into_iter().take(rest.len() + 1) {
+            chars.push(ALPHABET[(group >> shift) as usize & 0x3F]);
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod tables {
+    use super::{ALPHABET, DECODINGS, INVALID};
+
+    // indexed by the low nibble of a character, the set of high nibbles that
+    // make it part of the alphabet
+    pub(super) const LOW_NIBBLES: [u8; 16] = {
+        let mut nibbles = [0; 16];
+        let mut value = 0;
+        while value < 64 {
+            let b = ALPHABET[value];
+            nibbles[(b & 0x0F) as usize] |= 1 << (b >> 4);
+            value += 1;
+        }
+        nibbles
+    };
+
+    // indexed by the high nibble, the bit it has in `LOW_NIBBLES`
+    pub(super) const HIGH_NIBBLES: [u8; 16] = [1, 2, 4, 8, 16, 32, 64, 128, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    // the alphabet is contiguous within each run of eight characters, so a
+    // character decodes to itself minus the entry for bits 3 to 6
+    pub(super) const OFFSETS: [u8; 16] = {
+        let mut offsets = [0; 16];
+        let mut b = 0;
+        while b < 128 {
+            let value = DECODINGS[b];
+            if value != INVALID {
+                let offset = b as u8 - value;
+                let index = (b >> 3) & 0x0F;
+                assert!(offsets[index] == 0 || offsets[index] == offset);
+                offsets[index] = offset;
+            }
+            b += 1;
+        }
+        offsets
+    };
+
+    // the values at which the gap between a value and its character grows,
+    // and by how much; a value encodes to itself plus `FIRST` plus the steps
+    // at or below it
+    pub(super) const FIRST: u8 = ALPHABET[0];
+    pub(super) const STEPS: [(u8, u8); 8] = {
+        let mut steps = [(0, 0); 8];
+        let mut count = 0;
+        let mut value = 1;
+        while value < 64 {
+            let step = (ALPHABET[value] - ALPHABET[value - 1]) - 1;
+            if step != 0 {
+                steps[count] = (value as u8, step);
+                count += 1;
+            }
+            value += 1;
+        }
+        assert!(count == 8);
+        steps
+    };
+}
+
+mod simd {
+    // decodes whole blocks with simd, returning how many characters it decoded
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub(super) fn decode_blocks(chars: &[u8], bytes: &mut Vec<u8>) -> Result<usize, usize> {
+        if !super::x86::is_supported() {
+            return Ok(0);
+        }
+        // safety: the cpu supports the instructions `decode_blocks` is compiled for
+        unsafe { super::x86::decode_blocks(chars, bytes) }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    pub(super) fn decode_blocks(chars: &[u8], bytes: &mut Vec<u8>) -> Result<usize, usize> {
+        // safety: neon is part of every aarch64 cpu
+        unsafe { super::neon::decode_blocks(chars, bytes) }
+    }
+
+    #[cfg(not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+    pub(super) fn decode_blocks(_: &[u8], _: &mut Vec<u8>) -> Result<usize, usize> {
+        Ok(0)
+    }
+
+    // encodes whole blocks with simd, returning how many bytes it encoded
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub(super) fn encode_blocks(bytes: &[u8], chars: &mut Vec<u8>) -> usize {
+        if !super::x86::is_supported() {
+            return 0;
+        }
+        // safety: the cpu supports the instructions `encode_blocks` is compiled for
+        unsafe { super::x86::encode_blocks(bytes, chars) }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    pub(super) fn encode_blocks(bytes: &[u8], chars: &mut Vec<u8>) -> usize {
+        // safety: neon is part of every aarch64 cpu
+        unsafe { super::neon::encode_blocks(bytes, chars) }
+    }
+
+    #[cfg(not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+    pub(super) fn encode_blocks(_: &[u8], _: &mut Vec<u8>) -> usize {
+        0
+    }
+}
+
+// 16 characters to 12 bytes at a time with ssse3 shuffles as table lookups
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{
+        first_invalid,
+        tables::{FIRST, HIGH_NIBBLES, LOW_NIBBLES, OFFSETS, STEPS},
+    };
+
+    pub(super) fn is_supported() -> bool {
+        is_x86_feature_detected!("ssse3")
+    }
+
+    unsafe fn load_table(table: &[u8; 16]) -> __m128i {
+        _mm_loadu_si128(table.as_ptr() as *const __m128i)
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn decode_blocks(chars: &[u8], bytes: &mut Vec<u8>) -> Result<usize, usize> {
+        let low_nibbles = load_table(&LOW_NIBBLES);
+        let high_nibbles = load_table(&HIGH_NIBBLES);
+        let offsets = load_table(&OFFSETS);
+        let nibble = _mm_set1_epi8(0x0F);
+        // packs each four 6-bit values, first value highest, into 24 bits
+        let merge_pairs = _mm_set1_epi32(0x0140_0140);
+        let merge_quads = _mm_set1_epi32(0x0001_1000);
+        let pack = _mm_setr_epi8(2, 1, 0, 6, 5, 4, 10, 9, 8, 14, 13, 12, -1, -1, -1, -1);
+
+        let mut decoded = 0;
+        let mut block = [0u8; 16];
+        while decoded + 16 <= chars.len() {
+            let input = _mm_loadu_si128(chars[decoded..].as_ptr() as *const __m128i);
+
+            let high = _mm_and_si128(_mm_srli_epi16(input, 4), nibble);
+            let low = _mm_and_si128(input, nibble);
+            let valid = _mm_and_si128(
+                _mm_shuffle_epi8(low_nibbles, low),
+                _mm_shuffle_epi8(high_nibbles, high),
+            );
+            if _mm_movemask_epi8(_mm_cmpeq_epi8(valid, _mm_setzero_si128())) != 0 {
+                return Err(first_invalid(chars, decoded));
+            }
+
+            let index = _mm_and_si128(_mm_srli_epi16(input, 3), nibble);
+            let values = _mm_sub_epi8(input, _mm_shuffle_epi8(offsets, index));
+
+            let pairs = _mm_maddubs_epi16(values, merge_pairs);
+            let quads = _mm_madd_epi16(pairs, merge_quads);
+            let packed = _mm_shuffle_epi8(quads, pack);
+
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, packed);
+            bytes.extend_from_slice(&block[..12]);
+            decoded += 16;
+        }
+
+        Ok(decoded)
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn encode_blocks(bytes: &[u8], chars: &mut Vec<u8>) -> usize {
+        // spreads each three bytes over four lanes so the 6-bit values can be
+        // shifted into place
+        let spread = _mm_setr_epi8(1, 0, 2, 1, 4, 3, 5, 4, 7, 6, 8, 7, 10, 9, 11, 10);
+        let first = _mm_set1_epi8(FIRST as i8);
+
+        let mut encoded = 0;
+        let mut block = [0u8; 16];
+        // loads 16 bytes but uses only 12
+        while encoded + 16 <= bytes.len() {
+            let input = _mm_loadu_si128(bytes[encoded..].as_ptr() as *const __m128i);
+            let input = _mm_shuffle_epi8(input, spread);
+
+            let ac = _mm_mulhi_epu16(
+                _mm_and_si128(input, _mm_set1_epi32(0x0FC0_FC00)),
+                _mm_set1_epi32(0x0400_0040),
+            );
+            let bd = _mm_mullo_epi16(
+                _mm_and_si128(input, _mm_set1_epi32(0x003F_03F0)),
+                _mm_set1_epi32(0x0100_0010),
+            );
+            let values = _mm_or_si128(ac, bd);
+
+            let mut output = _mm_add_epi8(values, first);
+            for (value, step) in STEPS {
+                let above = _mm_cmpgt_epi8(values, _mm_set1_epi8(value as i8 - 1));
+                output = _mm_add_epi8(output, _mm_and_si128(above, _mm_set1_epi8(step as i8)));
+            }
+
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, output);
+            chars.extend_from_slice(&block);
+            encoded += 12;
+        }
+
+        encoded
+    }
+}
+
+// 64 characters to 48 bytes at a time, with interleaving loads and stores
+// doing the packing
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon {
+    use std::arch::aarch64::*;
+
+    use super::{
+        first_invalid,
+        tables::{FIRST, HIGH_NIBBLES, LOW_NIBBLES, OFFSETS, STEPS},
+    };
+
+    struct Tables {
+        low_nibbles: uint8x16_t,
+        high_nibbles: uint8x16_t,
+        offsets: uint8x16_t,
+    }
+
+    // the 6-bit values of `input`, or `None` if any is outside the alphabet
+    #[target_feature(enable = "neon")]
+    unsafe fn values(input: uint8x16_t, tables: &Tables) -> Option<uint8x16_t> {
+        let nibble = vdupq_n_u8(0x0F);
+        let valid = vandq_u8(
+            vqtbl1q_u8(tables.low_nibbles, vandq_u8(input, nibble)),
+            vqtbl1q_u8(tables.high_nibbles, vshrq_n_u8::<4>(input)),
+        );
+        if vminvq_u8(valid) == 0 {
+            return None;
+        }
+
+        let index = vandq_u8(vshrq_n_u8::<3>(input), nibble);
+        Some(vsubq_u8(input, vqtbl1q_u8(tables.offsets, index)))
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn decode_blocks(chars: &[u8], bytes: &mut Vec<u8>) -> Result<usize, usize> {
+        let tables = Tables {
+            low_nibbles: vld1q_u8(LOW_NIBBLES.as_ptr()),
+            high_nibbles: vld1q_u8(HIGH_NIBBLES.as_ptr()),
+            offsets: vld1q_u8(OFFSETS.as_ptr()),
+        };
+
+        let mut decoded = 0;
+        let mut block = [0u8; 48];
+        while decoded + 64 <= chars.len() {
+            // each lane holds every fourth character
+            let input = vld4q_u8(chars[decoded..].as_ptr());
+            let (Some(a), Some(b), Some(c), Some(d)) = (
+                values(input.0, &tables),
+                values(input.1, &tables),
+                values(input.2, &tables),
+                values(input.3, &tables),
+            ) else {
+                return Err(first_invalid(chars, decoded));
+            };
+
+            let packed = uint8x16x3_t(
+                vorrq_u8(vshlq_n_u8::<2>(a), vshrq_n_u8::<4>(b)),
+                vorrq_u8(vshlq_n_u8::<4>(b), vshrq_n_u8::<2>(c)),
+                vorrq_u8(vshlq_n_u8::<6>(c), d),
+            );
+            vst3q_u8(block.as_mut_ptr(), packed);
+            bytes.extend_from_slice(&block);
+            decoded += 64;
+        }
+
+        Ok(decoded)
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn characters(values: uint8x16_t) -> uint8x16_t {
+        let mut output = vaddq_u8(values, vdupq_n_u8(FIRST));
+        for (value, step) in STEPS {
+            let above = vcgeq_u8(values, vdupq_n_u8(value));
+            output = vaddq_u8(output, vandq_u8(above, vdupq_n_u8(step)));
+        }
+        output
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn encode_blocks(bytes: &[u8], chars: &mut Vec<u8>) -> usize {
+        let six_bits = vdupq_n_u8(0x3F);
+
+        let mut encoded = 0;
+        let mut block = [0u8; 64];
+        while encoded + 48 <= bytes.len() {
+            // each lane holds every third byte
+            let input = vld3q_u8(bytes[encoded..].as_ptr());
+            let a = vshrq_n_u8::<2>(input.0);
+            let b = vandq_u8(
+                vorrq_u8(vshlq_n_u8::<4>(input.0), vshrq_n_u8::<4>(input.1)),
+                six_bits,
+            );
+            let c = vandq_u8(
+                vorrq_u8(vshlq_n_u8::<2>(input.1), vshrq_n_u8::<6>(input.2)),
+                six_bits,
+            );
+            let d = vandq_u8(input.2, six_bits);
+
+            let output = uint8x16x4_t(characters(a), characters(b), characters(c), characters(d));
+            vst4q_u8(block.as_mut_ptr(), output);
+            chars.extend_from_slice(&block);
+            encoded += 48;
+        }
+
+        encoded
+    }
+}
@@ -0,0 +1,169 @@
+use std::io::{self, Write};
+
+use crate::{
+    crc::Crc16,
+    encode::{EncodeOptions, SixBitEncoder},
+    error::EncodeError,
+    rle::RleEncoder,
+    HQXHeader,
+};
+
+// encoded output is handed to the inner writer in chunks of about this size
+const FLUSH_THRESHOLD: usize = 8 * 1024;
+
+pub struct BinhexWriter<W: Write> {
+    inner: W,
+    header: HQXHeader,
+    options: EncodeOptions,
+    stage: Stage,
+    remaining: u64,
+    crc: Crc16,
+    rle: RleEncoder,
+    compressed: Vec<u8>,
+    encoder: SixBitEncoder,
+    encoded: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Data,
+    Resource,
+    Done,
+}
+
+impl<W: Write> BinhexWriter<W> {
+    // writes the file marker and header; fork bytes are then written in order,
+    // first `header.data_len` bytes of data fork, then the resource fork.
+    // `header.hc` is ignored: the header crc is computed from the other fields,
+    // and `header()` returns it
+    pub fn new(inner: W, header: HQXHeader) -> Result<BinhexWriter<W>, EncodeError> {
+        BinhexWriter::with_options(inner, header, EncodeOptions::default())
+    }
+
+    // like `new`, with `hc` ignored the same way
+    pub fn with_options(
+        inner: W,
+        header: HQXHeader,
+        options: EncodeOptions,
+    ) -> Result<BinhexWriter<W>, EncodeError> {
+        let header_bytes = header.to_bytes()?;
+        let hc = u16::from_be_bytes([
+            header_bytes[header_bytes.len() - 2],
+            header_bytes[header_bytes.len() - 1],
+        ]);
+        let header = HQXHeader { hc, ..header };
+
+        let mut writer = BinhexWriter {
+            inner,
+            remaining: header.data_len as u64,
+            header,
+            stage: Stage::Data,
+            crc: Crc16::new(),
+            rle: RleEncoder::new(),
+            compressed: Vec::new(),
+            encoder: SixBitEncoder::new(&options),
+            encoded: Vec::with_capacity(FLUSH_THRESHOLD + 128),
+            options,
+        };
+
+        writer.options.write_prologue(&mut writer.encoded);
+        writer.push_bytes(&header_bytes);
+        writer.finish_empty_forks();
+        writer.flush_encoded(false)?;
+
+        Ok(writer)
+    }
+
+    pub fn header(&self) -> &HQXHeader {
+        &self.header
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    // fails if fewer fork bytes were written than the header promised
+    pub fn finish(mut self) -> Result<W, EncodeError> {
+        if self.stage != Stage::Done {
+            return Err(EncodeError::ForkLengthMismatch);
+        }
+
+        self.rle.finish(&mut self.compressed);
+        self.encode_compressed();
+        self.encoder.finish(&mut self.encoded);
+
+        self.options.write_epilogue(&mut self.encoded);
+
+        self.flush_encoded(true)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.rle.push_all(bytes, &mut self.compressed);
+        self.encode_compressed();
+    }
+
+    fn encode_compressed(&mut self) {
+        for byte in self.compressed.drain(..) {
+            self.encoder.push(byte, &mut self.encoded);
+        }
+    }
+
+    // emits the crc of every fork that has received all of its bytes
+    fn finish_empty_forks(&mut self) {
+        while self.remaining == 0 && self.stage != Stage::Done {
+            let crc = self.crc.finish().to_be_bytes();
+            self.push_bytes(&crc);
+            self.crc = Crc16::new();
+
+            self.stage = match self.stage {
+                Stage::Data => {
+                    self.remaining = self.header.resource_len as u64;
+                    Stage::Resource
+                }
+                _ => Stage::Done,
+            };
+        }
+    }
+
+    fn flush_encoded(&mut self, force: bool) -> io::Result<()> {
+        if force || self.encoded.len() >= FLUSH_THRESHOLD {
+            self.inner.write_all(&self.encoded)?;
+            self.encoded.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BinhexWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.stage == Stage::Done {
+            return Err(EncodeError::ForkLengthMismatch.into());
+        }
+
+        let len = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let bytes = &buf[..len];
+
+        self.crc.update(bytes);
+        self.remaining -= len as u64;
+        self.push_bytes(bytes);
+        self.finish_empty_forks();
+        self.flush_encoded(false)?;
+
+        Ok(len)
+    }
+
+    // only flushes whole encoded characters; the final partial group and run
+    // are held back until `finish`
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_encoded(true)?;
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+
+use binhex4::{
+    applefile::{
+        apple_double_path, AppleFile, AppleFileKind, AppleFileVersion, DATA_FORK, FINDER_INFO,
+        REAL_NAME, RESOURCE_FORK,
+    },
+    error::AppleFileError,
+    HQX,
+};
+
+mod common;
+
+fn sample() -> HQX {
+    // a name that is not ASCII, to check it is stored in Mac Roman
+    common::sample_named("Café")
+}
+
+#[test]
+fn apple_single_keeps_every_entry() {
+    let hqx = sample();
+    for version in [AppleFileVersion::V1, AppleFileVersion::V2] {
+        let bytes = hqx
+            .borrow()
+            .to_apple_file(AppleFileKind::Single, version)
+            .to_bytes()
+            .unwrap();
+        let apple_single = AppleFile::parse(&bytes).unwrap();
+
+        assert_eq!(apple_single.kind, AppleFileKind::Single);
+        assert_eq!(apple_single.version, version);
+        let ids: Vec<_> = apple_single.entries.iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, [REAL_NAME, FINDER_INFO, DATA_FORK, RESOURCE_FORK]);
+        assert_eq!(apple_single.real_name(), Some(&b"Caf\x8E"[..]));
+        assert_eq!(apple_single.entry(FINDER_INFO).unwrap().len(), 32);
+        assert_eq!(apple_single.to_hqx(None).unwrap().vec, hqx.vec);
+    }
+}
+
+#[test]
+fn apple_double_leaves_out_the_data_fork() {
+    let hqx = sample();
+    let bytes = hqx
+        .borrow()
+        .to_apple_file(AppleFileKind::Double, AppleFileVersion::V2)
+        .to_bytes()
+        .unwrap();
+    let apple_double = AppleFile::parse(&bytes).unwrap();
+
+    assert_eq!(apple_double.kind, AppleFileKind::Double);
+    assert!(apple_double.data_fork().is_none());
+    assert_eq!(apple_double.resource_fork(), Some(&b"resources"[..]));
+    assert_eq!(apple_double.to_hqx(Some(b"data")).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn rejects_malformed_files() {
+    let bytes = sample()
+        .borrow()
+        .to_apple_file(AppleFileKind::Single, AppleFileVersion::V2)
+        .to_bytes()
+        .unwrap();
+
+    assert!(matches!(
+        AppleFile::parse(&bytes[..10]),
+        Err(AppleFileError::Truncated)
+    ));
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = 0xFF;
+    assert!(matches!(
+        AppleFile::parse(&bad_magic),
+        Err(AppleFileError::BadMagic)
+    ));
+    assert!(matches!(
+        AppleFile::parse(&bytes[..bytes.len() - 1]),
+        Err(AppleFileError::EntryOutOfBounds { id: RESOURCE_FORK })
+    ));
+}
+
+#[test]
+fn reads_back_what_write_apple_double_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Café");
+    let hqx = sample();
+    hqx.borrow().write_apple_double(&path).unwrap();
+    assert!(apple_double_path(&path).unwrap().exists());
+
+    assert_eq!(HQX::from_apple_double(&path).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn converts_host_names_without_a_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(format!("Café {}.txt", "x".repeat(80)));
+    fs::write(&path, "some text").unwrap();
+
+    let hqx = HQX::from_apple_double(&path).unwrap();
+    let hqx = hqx.borrow();
+    let name = hqx.name.to_bytes();
+    assert!(name.starts_with(b"Caf\x8E x"));
+    assert!(name.ends_with(b".txt"));
+    assert_eq!(name.len(), 63);
+    assert_eq!(hqx.file_type, *b"TEXT");
+}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use binhex4::{builder::HqxBuilder, decode::hexbin, error::BuildError, HQX};
+
+#[test]
+fn builds_from_borrowed_and_owned_forks() {
+    let data = b"hello".to_vec();
+    let borrowed = HQX::builder()
+        .name("Read Me")
+        .file_type(*b"TEXT")
+        .creator(*b"ttxt")
+        .finder_flags(0x0100)
+        .data_fork(&data[..])
+        .resource_fork(vec![1, 2, 3])
+        .build()
+        .unwrap();
+    let owned = HqxBuilder::new()
+        .name(String::from("Read Me"))
+        .file_type(*b"TEXT")
+        .creator(*b"ttxt")
+        .finder_flags(0x0100)
+        .data_fork(data.clone())
+        .resource_fork(Cow::Borrowed(&[1, 2, 3][..]))
+        .build()
+        .unwrap();
+    assert_eq!(borrowed.vec, owned.vec);
+
+    let decoded = hexbin(&borrowed.borrow().encode(), true).unwrap();
+    let decoded = decoded.borrow();
+    assert_eq!(decoded.name.to_bytes(), b"Read Me");
+    assert_eq!(decoded.file_type, *b"TEXT");
+    assert_eq!(decoded.author, *b"ttxt");
+    assert_eq!(decoded.data_fork.unwrap().data, b"hello");
+    assert_eq!(decoded.resource_fork.unwrap().data, [1, 2, 3]);
+}
+
+#[test]
+fn names_are_converted_to_mac_roman() {
+    let hqx = HQX::builder().name("Café™").build().unwrap();
+    assert_eq!(hqx.borrow().name.to_bytes(), b"Caf\x8E\xAA");
+    assert_eq!(hqx.borrow().unicode_name(), "Café™");
+
+    let hqx = HQX::builder()
+        .mac_roman_name(&b"Caf\x8E"[..])
+        .build()
+        .unwrap();
+    assert_eq!(hqx.borrow().unicode_name(), "Café");
+}
+
+#[test]
+fn rejects_bad_names() {
+    let cases = [
+        ("", BuildError::NameEmpty),
+        ("a:b", BuildError::NameContainsColon { offset: 1 }),
+        ("ab\0c", BuildError::NameContainsNul { offset: 2 }),
+        ("☃", BuildError::NameNotMacRoman { character: '☃' }),
+    ];
+    for (name, expected) in cases {
+        assert_eq!(HQX::builder().name(name).build().unwrap_err(), expected);
+    }
+}
+
+#[test]
+fn limits_name_length() {
+    let hfs = "x".repeat(63);
+    assert!(HQX::builder().name(&hfs[..]).build().is_ok());
+
+    let long = "x".repeat(64);
+    assert_eq!(
+        HQX::builder().name(&long[..]).build().unwrap_err(),
+        BuildError::NameTooLongForHfs { len: 64 }
+    );
+    assert!(HQX::builder()
+        .name(&long[..])
+        .hfs_name_limit(false)
+        .build()
+        .is_ok());
+
+    let too_long = "x".repeat(256);
+    assert_eq!(
+        HQX::builder()
+            .name(too_long)
+            .hfs_name_limit(false)
+            .build()
+            .unwrap_err(),
+        BuildError::NameTooLong { len: 256 }
+    );
+}
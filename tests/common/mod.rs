@@ -0,0 +1,62 @@
+// helpers shared by the integration tests and the benches; each of them uses
+// only some
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf};
+
+use binhex4::HQX;
+
+// a text file named `Read Me` with the given forks
+pub fn text_file(data: &[u8], resource: &[u8]) -> HQX {
+    HQX::builder()
+        .name("Read Me")
+        .file_type(*b"TEXT")
+        .creator(*b"ttxt")
+        .data_fork(data)
+        .resource_fork(resource)
+        .build()
+        .unwrap()
+}
+
+// a file with both forks and some Finder flags
+pub fn sample() -> HQX {
+    sample_named("Sample")
+}
+
+pub fn sample_named(name: &str) -> HQX {
+    HQX::builder()
+        .name(name)
+        .file_type(*b"APPL")
+        .creator(*b"SAMP")
+        .finder_flags(0x2000)
+        .data_fork(&b"data"[..])
+        .resource_fork(&b"resources"[..])
+        .build()
+        .unwrap()
+}
+
+pub fn resource_only() -> HQX {
+    HQX::builder()
+        .name("Tool")
+        .file_type(*b"APPL")
+        .creator(*b"TOOL")
+        .resource_fork(&b"CODE resources"[..])
+        .build()
+        .unwrap()
+}
+
+// the files in `test/hex`, by name
+pub fn test_files() -> Vec<(String, Vec<u8>)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/hex");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read(path).unwrap())
+        })
+        .collect();
+    files.sort();
+    assert!(!files.is_empty());
+    files
+}
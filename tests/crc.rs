@@ -0,0 +1,45 @@
+use binhex4::crc::{checksum_bitwise, Crc16};
+
+// deterministic bytes without pulling in a random number generator
+fn bytes(len: usize) -> Vec<u8> {
+    let mut state: u32 = 0x1234_5678;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn check_value() {
+    assert_eq!(Crc16::checksum(b"123456789"), 0x31C3);
+    assert_eq!(checksum_bitwise(b"123456789"), 0x31C3);
+}
+
+#[test]
+fn matches_bitwise_for_every_length() {
+    let data = bytes(1100);
+    for len in 0..data.len() {
+        let data = &data[..len];
+        assert_eq!(
+            Crc16::checksum(data),
+            checksum_bitwise(data),
+            "length {len}"
+        );
+    }
+}
+
+#[test]
+fn incremental_updates_match_one_shot() {
+    let data = bytes(3000);
+    let expected = checksum_bitwise(&data);
+    for split in [0, 1, 7, 63, 64, 129, 1000, 2999, 3000] {
+        let mut crc = Crc16::new();
+        crc.update(&data[..split]);
+        crc.update(&data[split..]);
+        assert_eq!(crc.finish(), expected, "split at {split}");
+    }
+}
@@ -0,0 +1,88 @@
+use binhex4::{
+    decode::{hexbin_with_mode, DecodeMode},
+    error::DecodeError,
+    sixbit, HQX,
+};
+
+mod common;
+
+// encodes an already run-length compressed stream
+fn encode_compressed(compressed: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![b':'];
+    encoded.extend_from_slice(&sixbit::encode(compressed));
+    encoded.push(b':');
+    encoded
+}
+
+fn decode(encoded: &[u8], mode: DecodeMode) -> Result<HQX, DecodeError> {
+    hexbin_with_mode(encoded, true, mode)
+}
+
+#[test]
+fn strict_mode_rejects_a_run_with_nothing_to_repeat() {
+    let mut compressed = vec![0x90, 0x05];
+    compressed.extend_from_slice(&common::text_file(b"hello", b"").vec);
+    let encoded = encode_compressed(&compressed);
+
+    assert!(matches!(
+        decode(&encoded, DecodeMode::Strict),
+        Err(DecodeError::BadRunLengthEncoding)
+    ));
+    // lenient mode repeats a zero byte, which the header then fails to parse
+    assert!(!matches!(
+        decode(&encoded, DecodeMode::Lenient),
+        Err(DecodeError::BadRunLengthEncoding)
+    ));
+}
+
+#[test]
+fn strict_mode_rejects_a_marker_with_no_count() {
+    let hqx = common::text_file(b"hello", b"");
+    assert!(!hqx.vec.contains(&0x90));
+    let mut compressed = hqx.vec.clone();
+    compressed.push(0x90);
+    let encoded = encode_compressed(&compressed);
+
+    assert!(matches!(
+        decode(&encoded, DecodeMode::Strict),
+        Err(DecodeError::BadRunLengthEncoding)
+    ));
+    assert_eq!(decode(&encoded, DecodeMode::Lenient).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn only_lenient_mode_skips_spaces_and_tabs() {
+    let hqx = common::text_file(b"hello", b"");
+    let mut encoded = hqx.borrow().encode();
+    let colon = encoded.iter().position(|b| *b == b':').unwrap();
+    encoded.insert(colon + 5, b' ');
+    encoded.insert(colon + 9, b'\t');
+
+    assert_eq!(decode(&encoded, DecodeMode::Lenient).unwrap().vec, hqx.vec);
+    match decode(&encoded, DecodeMode::Strict) {
+        Err(DecodeError::IllegalCharacter { byte, line, column }) => {
+            assert_eq!((byte, line, column), (b' ', 3, 6));
+        }
+        other => panic!("unexpected result: {:?}", other.map(|hqx| hqx.vec)),
+    }
+}
+
+#[test]
+fn characters_outside_the_alphabet_are_illegal_in_both_modes() {
+    for byte in [b'v', b'7', b'O', b'~', 0x00, 0x80, 0xFE] {
+        let mut encoded = common::text_file(b"hello", b"").borrow().encode();
+        let colon = encoded.iter().position(|b| *b == b':').unwrap();
+        encoded[colon + 3] = byte;
+
+        for mode in [DecodeMode::Strict, DecodeMode::Lenient] {
+            assert!(
+                matches!(
+                    decode(&encoded, mode),
+                    Err(DecodeError::IllegalCharacter { byte: found, line: 3, column: 4 })
+                        if found == byte
+                ),
+                "0x{byte:02X} in {mode:?} mode"
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    time::{Duration, SystemTime},
+};
+
+use binhex4::{
+    applefile::AppleFile,
+    extract::{ExtractOptions, MetadataFormat},
+    macbinary::MacBinary,
+    HQX,
+};
+
+mod common;
+
+#[test]
+fn extracts_resource_only_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let hqx = common::resource_only();
+
+    let written = hqx
+        .borrow()
+        .extract(dir.path(), &ExtractOptions::default())
+        .unwrap();
+    assert_eq!(
+        written,
+        [dir.path().join("Tool"), dir.path().join("._Tool")]
+    );
+    assert!(fs::read(&written[0]).unwrap().is_empty());
+
+    let sidecar = fs::read(&written[1]).unwrap();
+    let apple_double = AppleFile::parse(&sidecar).unwrap();
+    assert_eq!(apple_double.resource_fork(), Some(&b"CODE resources"[..]));
+
+    // and they come back the same way
+    assert_eq!(HQX::from_path(&written[0]).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn every_format_round_trips() {
+    for metadata in [
+        MetadataFormat::AppleDouble,
+        MetadataFormat::Netatalk,
+        MetadataFormat::ExtendedAttributes,
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let hqx = common::resource_only();
+        let options = ExtractOptions {
+            metadata,
+            ..ExtractOptions::default()
+        };
+        let written = match hqx.borrow().extract(dir.path(), &options) {
+            Ok(written) => written,
+            // not every file system has user attributes
+            Err(_) if metadata == MetadataFormat::ExtendedAttributes => continue,
+            Err(err) => panic!("{metadata:?}: {err}"),
+        };
+        assert_eq!(
+            HQX::from_path(&written[0]).unwrap().vec,
+            hqx.vec,
+            "{metadata:?}"
+        );
+    }
+}
+
+#[test]
+fn writes_resource_files_and_macbinary() {
+    let dir = tempfile::tempdir().unwrap();
+    let hqx = common::resource_only();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+    let options = ExtractOptions {
+        metadata: MetadataFormat::ResourceFile,
+        modified: Some(modified),
+        ..ExtractOptions::default()
+    };
+    let written = hqx.borrow().extract(dir.path(), &options).unwrap();
+    assert_eq!(written[1], dir.path().join("Tool.rsrc"));
+    assert_eq!(fs::read(&written[1]).unwrap(), b"CODE resources");
+    for path in &written {
+        assert_eq!(fs::metadata(path).unwrap().modified().unwrap(), modified);
+    }
+
+    let options = ExtractOptions {
+        metadata: MetadataFormat::MacBinary,
+        modified: Some(modified),
+        ..ExtractOptions::default()
+    };
+    let written = hqx.borrow().extract(dir.path(), &options).unwrap();
+    assert_eq!(written, [dir.path().join("Tool.bin")]);
+    let bytes = fs::read(&written[0]).unwrap();
+    let macbinary = MacBinary::parse(&bytes).unwrap();
+    assert_eq!(macbinary.resource, b"CODE resources");
+    assert_eq!(macbinary.modified, 1_000_000_000 + 2_082_844_800);
+}
+
+#[test]
+fn refuses_to_overwrite_unless_told() {
+    let dir = tempfile::tempdir().unwrap();
+    let hqx = common::resource_only();
+    fs::write(dir.path().join("._Tool"), "keep me").unwrap();
+
+    let err = hqx
+        .borrow()
+        .extract(dir.path(), &ExtractOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    // nothing was written, not even the data fork
+    assert!(!dir.path().join("Tool").exists());
+    assert_eq!(fs::read(dir.path().join("._Tool")).unwrap(), b"keep me");
+
+    let options = ExtractOptions {
+        overwrite: true,
+        ..ExtractOptions::default()
+    };
+    hqx.borrow().extract(dir.path(), &options).unwrap();
+    assert_ne!(fs::read(dir.path().join("._Tool")).unwrap(), b"keep me");
+}
@@ -0,0 +1,27 @@
+use std::ffi::CString;
+
+use binhex4::filename::FileNameMapper;
+
+#[test]
+fn numbers_names_that_collide() {
+    let mut mapper = FileNameMapper::new();
+    let name = CString::new("Read Me.txt").unwrap();
+    assert_eq!(mapper.map(&name), "Read Me.txt");
+    assert_eq!(mapper.map(&name), "Read Me 2.txt");
+    let upper = CString::new("READ ME.TXT").unwrap();
+    assert_eq!(mapper.map(&upper), "READ ME 3.TXT");
+}
+
+#[test]
+fn numbers_names_with_long_extensions() {
+    let mut mapper = FileNameMapper::new();
+    let name = CString::new(format!("a.{}", "x".repeat(253))).unwrap();
+
+    let first = mapper.map(&name);
+    let second = mapper.map(&name);
+    let third = mapper.map(&name);
+    assert_eq!(first.len(), 255);
+    assert_eq!(second, format!("a 2.{}", "x".repeat(251)));
+    assert_eq!(third, format!("a 3.{}", "x".repeat(251)));
+    assert!([second, third].iter().all(|name| name.len() == 255));
+}
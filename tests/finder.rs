@@ -0,0 +1,153 @@
+use std::ffi::CString;
+
+use binhex4::{encode::binhex, finder::FinderFlags, HQXConfig, HQX};
+
+// every flag with its getter and setter
+type Accessors = (u16, fn(&FinderFlags) -> bool, fn(&mut FinderFlags, bool));
+
+const ACCESSORS: &[Accessors] = &[
+    (
+        FinderFlags::IS_ON_DESK,
+        FinderFlags::is_on_desk,
+        FinderFlags::set_on_desk,
+    ),
+    (
+        FinderFlags::REQUIRE_SWITCH_LAUNCH,
+        FinderFlags::requires_switch_launch,
+        FinderFlags::set_requires_switch_launch,
+    ),
+    (
+        FinderFlags::IS_SHARED,
+        FinderFlags::is_shared,
+        FinderFlags::set_shared,
+    ),
+    (
+        FinderFlags::HAS_NO_INITS,
+        FinderFlags::has_no_inits,
+        FinderFlags::set_has_no_inits,
+    ),
+    (
+        FinderFlags::HAS_BEEN_INITED,
+        FinderFlags::has_been_inited,
+        FinderFlags::set_has_been_inited,
+    ),
+    (
+        FinderFlags::CHANGED,
+        FinderFlags::is_changed,
+        FinderFlags::set_changed,
+    ),
+    (
+        FinderFlags::HAS_CUSTOM_ICON,
+        FinderFlags::has_custom_icon,
+        FinderFlags::set_has_custom_icon,
+    ),
+    (
+        FinderFlags::IS_STATIONERY,
+        FinderFlags::is_stationery,
+        FinderFlags::set_stationery,
+    ),
+    (
+        FinderFlags::NAME_LOCKED,
+        FinderFlags::is_name_locked,
+        FinderFlags::set_name_locked,
+    ),
+    (
+        FinderFlags::HAS_BUNDLE,
+        FinderFlags::has_bundle,
+        FinderFlags::set_has_bundle,
+    ),
+    (
+        FinderFlags::IS_INVISIBLE,
+        FinderFlags::is_invisible,
+        FinderFlags::set_invisible,
+    ),
+    (
+        FinderFlags::IS_ALIAS,
+        FinderFlags::is_alias,
+        FinderFlags::set_alias,
+    ),
+];
+
+#[test]
+fn each_accessor_reads_and_writes_only_its_bit() {
+    for (bit, is, set) in ACCESSORS.iter().copied() {
+        let mut flags = FinderFlags::default();
+        assert!(!is(&flags));
+        set(&mut flags, true);
+        assert!(is(&flags));
+        assert_eq!(flags.bits(), bit);
+
+        let mut flags = FinderFlags::new(0xFFFF);
+        assert!(is(&flags));
+        set(&mut flags, false);
+        assert!(!is(&flags));
+        assert_eq!(flags.bits(), !bit);
+    }
+}
+
+#[test]
+fn converts_to_and_from_big_endian_bytes() {
+    let flags = FinderFlags::from_bytes([0x20, 0x01]);
+    assert_eq!(flags.bits(), 0x2001);
+    assert_eq!(flags.to_bytes(), [0x20, 0x01]);
+    assert_eq!(FinderFlags::from([0x20, 0x01]), flags);
+    assert_eq!(FinderFlags::from(0x2001), flags);
+    assert_eq!(format!("{:?}", flags), "FinderFlags(0x2001)");
+}
+
+#[test]
+fn color_stays_inside_its_bits() {
+    let mut flags = FinderFlags::new(!FinderFlags::COLOR);
+    for color in 0..8 {
+        flags.set_color(color);
+        assert_eq!(flags.color(), color);
+        assert_eq!(flags.bits() | FinderFlags::COLOR, 0xFFFF);
+    }
+
+    // only the low three bits of a color are kept
+    flags.set_color(0xFA);
+    assert_eq!(flags.color(), 2);
+    assert_eq!(flags.bits(), 0xFFF5);
+
+    let mut flags = FinderFlags::new(0xFFFF);
+    flags.set_color(0);
+    assert_eq!(flags.bits(), !FinderFlags::COLOR);
+}
+
+#[test]
+fn sanitized_clears_exactly_the_binhex_bits() {
+    assert_eq!(FinderFlags::BINHEX_CLEARED, 0x0301);
+    assert_eq!(
+        FinderFlags::new(0xFFFF).sanitized().bits(),
+        !FinderFlags::BINHEX_CLEARED
+    );
+    for bit in (0..16).map(|n| 1u16 << n) {
+        let kept = FinderFlags::new(bit).sanitized().bits() == bit;
+        assert_eq!(kept, bit & FinderFlags::BINHEX_CLEARED == 0, "{bit:#06X}");
+    }
+}
+
+#[test]
+fn binhex_writes_sanitized_flags() {
+    let hqx = binhex(HQXConfig {
+        name: Some(CString::new("Read Me").unwrap()),
+        file_type: None,
+        author: None,
+        flags: Some(FinderFlags::new(0x2343)),
+        data: None,
+        resource: None,
+    })
+    .unwrap();
+
+    // after the name length, the name and its null, the type and the creator
+    let at = 1 + 8 + 4 + 4;
+    assert_eq!(hqx.vec[at..at + 2], [0x20, 0x42]);
+    assert_eq!(hqx.borrow().flags.bits(), 0x2042);
+
+    let built = HQX::builder()
+        .name("Read Me")
+        .finder_flags(0x2343)
+        .build()
+        .unwrap();
+    assert_eq!(built.borrow().flags.bits(), 0x2042);
+}
@@ -0,0 +1,94 @@
+use binhex4::{
+    error::FourCharCodeError,
+    fourcc::{lookup, lookup_extension, FourCharCode},
+};
+
+#[test]
+fn parses_four_mac_roman_characters() {
+    assert_eq!("GIFf".parse::<FourCharCode>().unwrap(), *b"GIFf");
+    assert_eq!("XLS ".parse::<FourCharCode>().unwrap(), *b"XLS ");
+    // one byte each in Mac OS Roman, though not in UTF-8
+    assert_eq!("café".parse::<FourCharCode>().unwrap(), *b"caf\x8E");
+    assert_eq!(
+        "π•∞ø".parse::<FourCharCode>().unwrap(),
+        *b"\xB9\xA5\xB0\xBF"
+    );
+}
+
+#[test]
+fn rejects_other_lengths_and_characters() {
+    for s in ["", "GIF", "GIFf!", "caféé"] {
+        assert!(
+            matches!(s.parse::<FourCharCode>(), Err(FourCharCodeError::BadLength)),
+            "{s:?}"
+        );
+    }
+    for s in ["日本語!", "ab😀c", "GIFŁ"] {
+        assert!(
+            matches!(
+                s.parse::<FourCharCode>(),
+                Err(FourCharCodeError::NotMacRoman)
+            ),
+            "{s:?}"
+        );
+    }
+    assert_eq!(
+        FourCharCodeError::BadLength.to_string(),
+        "code must be exactly four characters"
+    );
+    assert_eq!(
+        FourCharCodeError::NotMacRoman.to_string(),
+        "code has characters outside of Mac OS Roman"
+    );
+}
+
+#[test]
+fn displays_high_bit_codes_in_mac_roman() {
+    assert_eq!(FourCharCode(*b"TEXT").to_string(), "TEXT");
+    assert_eq!(FourCharCode(*b"caf\x8E").to_string(), "café");
+    assert_eq!(FourCharCode(*b"\xB9\xA5\xB0\xBF").to_string(), "π•∞ø");
+    assert_eq!(
+        format!("{:?}", FourCharCode(*b"caf\x8E")),
+        "FourCharCode(\"café\")"
+    );
+
+    // display and parse are inverses
+    for code in [*b"TEXT", *b"caf\x8E", *b"\xB9\xA5\xB0\xBF"] {
+        let code = FourCharCode(code);
+        assert_eq!(code.to_string().parse::<FourCharCode>().unwrap(), code);
+    }
+}
+
+#[test]
+fn lookup_matches_the_creator_where_one_is_given() {
+    let kind = |file_type: &[u8; 4], creator: &[u8; 4]| {
+        lookup(FourCharCode(*file_type), FourCharCode(*creator)).map(|kind| kind.mime_type)
+    };
+
+    assert_eq!(kind(b"WORD", b"MSWD"), Some("application/msword"));
+    assert_eq!(kind(b"WORD", b"MACA"), Some("application/x-macwrite"));
+    // no catch-all for `WORD`, since the creator decides what it is
+    assert_eq!(kind(b"WORD", b"ttxt"), None);
+
+    // catch-alls match any creator
+    assert_eq!(kind(b"TEXT", b"ttxt"), Some("text/plain"));
+    assert_eq!(kind(b"TEXT", b"R*ch"), Some("text/plain"));
+    assert_eq!(kind(b"text", b"ttxt"), None);
+}
+
+#[test]
+fn lookup_extension_takes_the_first_match() {
+    let kind = |extension| lookup_extension(extension).map(|kind| kind.file_type);
+
+    // `doc` is listed for WORD, W6BN and W8BN
+    assert_eq!(kind("doc"), Some(FourCharCode(*b"WORD")));
+    assert_eq!(kind("DOC"), Some(FourCharCode(*b"WORD")));
+    assert_eq!(kind("sit"), Some(FourCharCode(*b"SIT!")));
+    assert_eq!(kind("bmp"), Some(FourCharCode(*b"BMP ")));
+    assert_eq!(kind("txt"), Some(FourCharCode(*b"TEXT")));
+    assert_eq!(kind("xyz"), None);
+    assert_eq!(kind(""), None);
+
+    let doc = lookup_extension("doc").unwrap();
+    assert_eq!(doc.creator, Some(FourCharCode(*b"MSWD")));
+}
@@ -0,0 +1,92 @@
+use std::fs;
+
+use binhex4::{
+    applefile::{AppleFileKind, AppleFileVersion},
+    sidecar::netatalk_path,
+    HQX,
+};
+
+mod common;
+
+#[test]
+fn guesses_type_from_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.TXT");
+    fs::write(&path, "some text").unwrap();
+
+    let hqx = HQX::from_path(&path).unwrap();
+    let hqx = hqx.borrow();
+    assert_eq!(hqx.name.to_bytes(), b"notes.TXT");
+    assert_eq!(hqx.file_type, *b"TEXT");
+    assert_eq!(hqx.data_fork.unwrap().data, b"some text");
+    assert!(hqx.resource_fork.is_none());
+}
+
+#[test]
+fn maps_host_names_to_mac_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let long = format!("{}:café.txt", "x".repeat(80));
+    let path = dir.path().join(&long);
+    fs::write(&path, "").unwrap();
+
+    let hqx = HQX::from_path(&path).unwrap();
+    let name = hqx.borrow().name.to_bytes().to_vec();
+    assert_eq!(name.len(), 63);
+    assert!(name.starts_with(b"xxx"));
+    assert!(name.ends_with(b".txt"));
+
+    let path = dir.path().join("a:b.txt");
+    fs::write(&path, "").unwrap();
+    let hqx = HQX::from_path(&path).unwrap();
+    assert_eq!(hqx.borrow().name.to_bytes(), b"a/b.txt");
+}
+
+#[test]
+fn reads_apple_double_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Sample");
+    let original = common::sample();
+    original.borrow().write_apple_double(&path).unwrap();
+
+    let hqx = HQX::from_path(&path).unwrap();
+    assert_eq!(hqx.vec, original.vec);
+}
+
+#[test]
+fn reads_netatalk_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Sample");
+    let original = common::sample();
+    let original_ref = original.borrow();
+
+    fs::write(&path, original_ref.data_fork.as_ref().unwrap().data).unwrap();
+    let sidecar = netatalk_path(&path).unwrap();
+    fs::create_dir(sidecar.parent().unwrap()).unwrap();
+    let apple_double = original_ref
+        .to_apple_file(AppleFileKind::Double, AppleFileVersion::V2)
+        .to_bytes()
+        .unwrap();
+    fs::write(&sidecar, apple_double).unwrap();
+
+    let hqx = HQX::from_path(&path).unwrap();
+    assert_eq!(hqx.vec, original.vec);
+}
+
+#[cfg(unix)]
+#[test]
+fn reads_extended_attributes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Sample");
+    fs::write(&path, "data").unwrap();
+
+    let mut finder_info = [0; 32];
+    finder_info[..10].copy_from_slice(b"APPLSAMP\x20\x00");
+    // not every file system has user attributes
+    if xattr::set(&path, "user.com.apple.FinderInfo", &finder_info).is_err() {
+        return;
+    }
+    xattr::set(&path, "user.com.apple.ResourceFork", b"resources").unwrap();
+
+    let hqx = HQX::from_path(&path).unwrap();
+    assert_eq!(hqx.vec, common::sample().vec);
+}
@@ -0,0 +1,96 @@
+use binhex4::{
+    decode::hexbin_all,
+    error::{CRCVerificationError, DecodeError},
+};
+
+mod common;
+
+const MARKER: &str = "(This file must be converted with BinHex 4.0)";
+
+fn mail(subject: &str) -> String {
+    format!("From: poster@example.com\r\nSubject: {subject}\r\n\r\nHere it is.\r\n\r\n")
+}
+
+// three archives, each behind its own mail headers, and where each one starts
+fn mailbox() -> (String, Vec<Vec<u8>>, Vec<usize>) {
+    let mut input = String::new();
+    let mut files = Vec::new();
+    let mut offsets = Vec::new();
+    for (n, data) in [&b"first"[..], b"second", b"third"].into_iter().enumerate() {
+        let hqx = common::text_file(data, &[1, 2, 3]);
+        input.push_str(&mail(&format!("archive {}", n + 1)));
+        offsets.push(input.len());
+        input.push_str(&String::from_utf8(hqx.borrow().encode()).unwrap());
+        files.push(hqx.vec);
+    }
+    (input, files, offsets)
+}
+
+#[test]
+fn yields_every_archive_with_its_offset() {
+    let (input, files, offsets) = mailbox();
+
+    let decoded: Vec<_> = hexbin_all(input.as_bytes(), true).collect();
+    assert_eq!(decoded.len(), 3);
+    for ((offset, hqx), (vec, expected)) in decoded.into_iter().zip(files.iter().zip(offsets)) {
+        assert_eq!(offset, expected);
+        assert!(input[offset..].starts_with(MARKER));
+        assert_eq!(&hqx.unwrap().vec, vec);
+    }
+}
+
+#[test]
+fn a_damaged_archive_does_not_affect_the_others() {
+    let (input, files, offsets) = mailbox();
+
+    // change a character in the first archive's data fork
+    let mut input = input.into_bytes();
+    let colon = offsets[0] + input[offsets[0]..].iter().position(|b| *b == b':').unwrap();
+    let target = colon + 40;
+    input[target] = if input[target] == b'A' { b'B' } else { b'A' };
+
+    let decoded: Vec<_> = hexbin_all(&input, true).collect();
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded[0].0, offsets[0]);
+    assert!(matches!(
+        decoded[0].1,
+        Err(DecodeError::CRCVerificationError(
+            CRCVerificationError::Data
+        ))
+    ));
+    for ((offset, hqx), (vec, expected)) in decoded[1..]
+        .iter()
+        .zip(files[1..].iter().zip(&offsets[1..]))
+    {
+        assert_eq!(offset, expected);
+        assert_eq!(&hqx.as_ref().unwrap().vec, vec);
+    }
+}
+
+#[test]
+fn errors_report_positions_in_the_whole_input() {
+    let (input, _, offsets) = mailbox();
+
+    // an illegal character at the start of the second archive's data
+    let mut input = input.into_bytes();
+    let colon = offsets[1] + input[offsets[1]..].iter().position(|b| *b == b':').unwrap();
+    input[colon + 1] = b'~';
+    let line = input[..colon].iter().filter(|b| **b == b'\n').count() + 1;
+
+    let decoded: Vec<_> = hexbin_all(&input, true).collect();
+    assert!(decoded[0].1.is_ok());
+    match &decoded[1].1 {
+        Err(DecodeError::IllegalCharacter {
+            byte,
+            line: at,
+            column,
+        }) => {
+            assert_eq!((*byte, *at, *column), (b'~', line, 2));
+        }
+        other => panic!(
+            "unexpected result: {:?}",
+            other.as_ref().map(|hqx| &hqx.vec)
+        ),
+    }
+    assert!(decoded[2].1.is_ok());
+}
@@ -0,0 +1,88 @@
+use binhex4::{
+    decode::DecodeMode,
+    encode::{EncodeOptions, LineEnding},
+    layout::hexbin_with_layout,
+    sixbit, HQX,
+};
+
+mod common;
+
+const ALPHABET: &[u8] = b"!\"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr";
+const RLE_MARKER_BYTE: u8 = 0x90;
+
+fn sample() -> HQX {
+    let resource: Vec<u8> = [0; 40].into_iter().chain(0..=255).collect();
+    common::text_file(b"hello, woooooooorld \x90\x90\x90", &resource)
+}
+
+// the bytes as they are, with only the markers escaped
+fn escape(bytes: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::new();
+    for b in bytes.iter().copied() {
+        escaped.push(b);
+        if b == RLE_MARKER_BYTE {
+            escaped.push(0);
+        }
+    }
+    escaped
+}
+
+fn round_trip(input: &[u8], hqx: &HQX) {
+    let (decoded, layout) = hexbin_with_layout(input, true, DecodeMode::default()).unwrap();
+    assert_eq!(decoded.vec, hqx.vec);
+    assert_eq!(decoded.borrow().encode_with_layout(&layout), input);
+}
+
+#[test]
+fn keeps_preamble_trailer_and_line_breaks() {
+    let hqx = sample();
+    let options = EncodeOptions {
+        line_length: 37,
+        line_ending: LineEnding::Cr,
+        preamble: b"From: someone\r\rsent with a note\r".to_vec(),
+        short_first_line: true,
+        ..EncodeOptions::default()
+    };
+    let mut input = hqx.borrow().encode_with(&options);
+    input.extend_from_slice(b"\r-- \rsignature\r");
+
+    let (_, layout) = hexbin_with_layout(&input, true, DecodeMode::default()).unwrap();
+    assert_eq!(layout.line_length(), Some(37));
+    assert_eq!(layout.line_ending(), Some(&b"\r"[..]));
+    assert_eq!(layout.trailer, b"\r\r-- \rsignature\r");
+    round_trip(&input, &hqx);
+}
+
+#[test]
+fn keeps_runs_the_encoder_would_not_choose() {
+    let hqx = sample();
+
+    // no runs at all
+    let literal = escape(&hqx.vec);
+    // the run of zeros split in two, and a marker left dangling at the end
+    let zeros = hqx.vec.len() - 2 - 256 - 40;
+    let mut split = escape(&hqx.vec[..zeros + 1]);
+    split.extend_from_slice(&[RLE_MARKER_BYTE, 20, RLE_MARKER_BYTE, 21]);
+    split.extend(escape(&hqx.vec[zeros + 40..]));
+    split.push(RLE_MARKER_BYTE);
+
+    for compressed in [literal, split] {
+        let mut chars = sixbit::encode(&compressed);
+        // set the unused low bits of the last character
+        if compressed.len() % 3 != 0 {
+            let last = chars.last_mut().unwrap();
+            let value = ALPHABET.iter().position(|c| c == last).unwrap();
+            *last = ALPHABET[value | 0x03];
+        }
+
+        let mut input = b"(This file must be converted with BinHex 4.0)\n\n:".to_vec();
+        for line in chars.chunks(23) {
+            input.extend_from_slice(line);
+            input.push(b'\n');
+        }
+        input.extend_from_slice(b":\n");
+
+        assert_ne!(input, hqx.borrow().encode());
+        round_trip(&input, &hqx);
+    }
+}
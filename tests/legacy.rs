@@ -0,0 +1,201 @@
+use binhex4::{
+    crc::Crc16,
+    decode::{hexbin, hexbin_all, DecodeMode},
+    error::{CRCVerificationError, DecodeError},
+    layout::hexbin_with_layout,
+    parse::{detect, Format},
+    HQX,
+};
+
+mod common;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn sum(bytes: &[u8]) -> u16 {
+    bytes
+        .iter()
+        .fold(0u16, |sum, b| sum.wrapping_add(*b as u16))
+}
+
+fn compressed(bytes: &[u8]) -> String {
+    bytes
+        .chunks(3)
+        .flat_map(|chunk| {
+            let [a, b, c] = [0, 1, 2].map(|k| chunk.get(k).copied().unwrap_or(0));
+            let values = [
+                a >> 2,
+                (a & 0x03) << 4 | b >> 4,
+                (b & 0x0F) << 2 | c >> 6,
+                c & 0x3F,
+            ];
+            values
+                .into_iter()
+                .take(chunk.len() + 1)
+                .map(|value| (value + 0x20) as char)
+        })
+        .collect()
+}
+
+fn hex_file(data: &[u8], resource: &[u8]) -> String {
+    format!(
+        "Read Me\r#TEXTttxt$2000\r***RESOURCE FORK\r{}\r***END OF DATA\r***CHECKSUM:{:04X}\r\
+         ***DATA FORK\r{}\r{}\r***END OF DATA\r***CHECKSUM:{:04X}\r",
+        hex(resource),
+        sum(resource),
+        hex(&data[..data.len() / 2]),
+        hex(&data[data.len() / 2..]),
+        sum(data),
+    )
+}
+
+#[test]
+fn decodes_hex_files() {
+    let input = hex_file(b"hello, world", &[1, 2, 3]);
+    assert_eq!(detect(input.as_bytes()), Some(Format::Hex));
+
+    let hqx = hexbin(input.as_bytes(), true).unwrap();
+    let hqx = hqx.borrow();
+    assert_eq!(hqx.name.to_bytes(), b"Read Me");
+    assert_eq!(hqx.file_type, *b"TEXT");
+    assert_eq!(hqx.author, *b"ttxt");
+    assert_eq!(hqx.flags.to_bytes(), [0x20, 0x00]);
+    assert_eq!(hqx.data_fork.unwrap().data, b"hello, world");
+    assert_eq!(hqx.resource_fork.unwrap().data, [1, 2, 3]);
+}
+
+#[test]
+fn decodes_compressed_hex_files() {
+    // lengths that leave no, one and two bytes in the last group
+    for data in [&b"hello!"[..], b"hello, world", b"hello, worlds"] {
+        let input = format!(
+            "(This file must be converted with BinHex 3.0)\n\nnotes\n#TEXTttxt\n\
+             ***COMPRESSED\n***DATA FORK\n{}\n***END OF DATA\n***CRC:{:04X}\n",
+            compressed(data),
+            Crc16::checksum(data),
+        );
+        assert_eq!(detect(input.as_bytes()), Some(Format::CompressedHex));
+
+        let hqx = hexbin(input.as_bytes(), true).unwrap();
+        let hqx = hqx.borrow();
+        assert_eq!(hqx.name.to_bytes(), b"notes");
+        assert_eq!(hqx.data_fork.unwrap().data, data);
+        assert!(hqx.resource_fork.is_none());
+    }
+}
+
+#[test]
+fn bad_checksums_fail_only_when_verifying() {
+    let input = hex_file(b"hello, world", &[]).replace("726C64", "726C65");
+
+    assert!(matches!(
+        hexbin(input.as_bytes(), true),
+        Err(DecodeError::CRCVerificationError(
+            CRCVerificationError::Data
+        ))
+    ));
+    let hqx = hexbin(input.as_bytes(), false).unwrap();
+    assert_eq!(hqx.borrow().data_fork.unwrap().data, b"hello, worle");
+}
+
+#[test]
+fn illegal_hex_digits_keep_their_position() {
+    let input = hex_file(b"hello, world", &[]).replace("68656C6C6F2C", "68656C6X6F2C");
+
+    match hexbin(input.as_bytes(), true) {
+        Err(DecodeError::IllegalCharacter { byte, line, column }) => {
+            assert_eq!((byte, line, column), (b'X', 8, 8));
+        }
+        other => panic!("unexpected result: {:?}", other.map(|hqx| hqx.vec)),
+    }
+}
+
+#[test]
+fn unwraps_macbinary_in_binhex5_files() {
+    let original = common::text_file(b"hello", &[1, 2, 3]);
+    let macbinary = original.borrow().to_macbinary().unwrap();
+    let wrapped = HQX::builder()
+        .name("Read Me.bin")
+        .data_fork(macbinary)
+        .build()
+        .unwrap();
+
+    let encoded = wrapped.borrow().encode();
+    assert_eq!(detect(&encoded), Some(Format::BinHex4));
+
+    let encoded = String::from_utf8(encoded)
+        .unwrap()
+        .replace("BinHex 4.0)", "BinHex 5.0)");
+    assert_eq!(detect(encoded.as_bytes()), Some(Format::BinHex5));
+
+    let hqx = hexbin(encoded.as_bytes(), true).unwrap();
+    let hqx = hqx.borrow();
+    assert_eq!(hqx.name.to_bytes(), b"Read Me");
+    assert_eq!(hqx.file_type, *b"TEXT");
+    assert_eq!(hqx.data_fork.unwrap().data, b"hello");
+    assert_eq!(hqx.resource_fork.unwrap().data, [1, 2, 3]);
+}
+
+#[test]
+fn banners_do_not_hide_binhex4_files() {
+    let hqx = common::text_file(b"hello", b"");
+    let encoded = String::from_utf8(hqx.borrow().encode()).unwrap();
+    let banner = "#########\n*** Posted to comp.binaries.mac ***\n#########\n\n";
+
+    let with_marker = format!("{banner}{encoded}");
+    let without_marker = format!(
+        "{banner}{}",
+        encoded.replace("(This file must be converted with BinHex 4.0)", "")
+    );
+    for input in [with_marker, without_marker] {
+        let input = input.as_bytes();
+        assert_eq!(detect(input), Some(Format::BinHex4));
+
+        let decoded = hexbin(input, true).unwrap();
+        assert_eq!(decoded.vec, hqx.vec);
+        let (_, decoded) = hexbin_all(input, true).next().unwrap();
+        assert_eq!(decoded.unwrap().vec, hqx.vec);
+        let (decoded, _) = hexbin_with_layout(input, true, DecodeMode::default()).unwrap();
+        assert_eq!(decoded.vec, hqx.vec);
+    }
+}
+
+// the files under test/legacy were made from the published descriptions of
+// each format, independently of the helpers above, not by BinHex itself
+fn fixture(name: &str) -> Vec<u8> {
+    std::fs::read(format!(
+        "{}/test/legacy/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+    .unwrap()
+}
+
+#[test]
+fn decodes_fixture_files() {
+    let data = [
+        &b"The quick brown fox jumps over the lazy dog.\r".repeat(3)[..],
+        &[0x90, 0x90, 0x00, 0xFF],
+    ]
+    .concat();
+    let resource: Vec<u8> = (0..=255).step_by(3).collect();
+
+    for (name, format) in [
+        ("sample.hex", Format::Hex),
+        ("sample.hcx", Format::CompressedHex),
+        ("sample5.hqx", Format::BinHex5),
+    ] {
+        let input = fixture(name);
+        assert_eq!(detect(&input), Some(format), "{name}");
+
+        let hqx = hexbin(&input, true).unwrap_or_else(|err| panic!("{name}: {err}"));
+        let hqx = hqx.borrow();
+        assert_eq!(hqx.name.to_bytes(), b"Sample Text", "{name}");
+        assert_eq!(hqx.file_type, *b"TEXT", "{name}");
+        assert_eq!(hqx.author, *b"ttxt", "{name}");
+        assert_eq!(hqx.flags.bits(), 0x2000, "{name}");
+        assert_eq!(hqx.data_fork.unwrap().data, data, "{name}");
+        assert_eq!(hqx.resource_fork.unwrap().data, resource, "{name}");
+    }
+}
@@ -0,0 +1,95 @@
+use binhex4::{
+    crc::Crc16,
+    error::MacBinaryError,
+    macbinary::{MacBinary, MacBinaryVersion},
+};
+
+mod common;
+
+fn sample(version: MacBinaryVersion) -> MacBinary<'static> {
+    MacBinary {
+        version,
+        name: b"Read Me",
+        file_type: (*b"TEXT").into(),
+        author: (*b"ttxt").into(),
+        flags: 0x2100.into(),
+        vertical: 40,
+        horizontal: 80,
+        folder: 0,
+        protected: true,
+        created: 3_000_000_000,
+        modified: 3_000_000_600,
+        script: 0,
+        extended_flags: 0,
+        data: b"hello, world",
+        resource: &[1; 300],
+        comment: b"",
+    }
+}
+
+#[test]
+fn each_version_round_trips() {
+    for version in [MacBinaryVersion::II, MacBinaryVersion::III] {
+        let mut original = sample(version);
+        original.comment = b"a comment";
+        if version == MacBinaryVersion::III {
+            original.script = 1;
+            original.extended_flags = 0x04;
+        }
+
+        let bytes = original.to_bytes().unwrap();
+        assert_eq!(bytes.len() % 128, 0);
+        assert_eq!(MacBinary::parse(&bytes).unwrap(), original);
+    }
+
+    // version 1 has no room for the low byte of the flags or a comment
+    let bytes = sample(MacBinaryVersion::I).to_bytes().unwrap();
+    let parsed = MacBinary::parse(&bytes).unwrap();
+    assert_eq!(parsed.version, MacBinaryVersion::I);
+    assert_eq!(parsed.flags.to_bytes(), [0x21, 0x00]);
+    assert_eq!(parsed.data, b"hello, world");
+    assert_eq!(parsed.resource, [1; 300]);
+}
+
+#[test]
+fn header_crc_covers_the_first_124_bytes() {
+    let bytes = sample(MacBinaryVersion::II).to_bytes().unwrap();
+    let crc = u16::from_be_bytes([bytes[124], bytes[125]]);
+    assert_eq!(crc, Crc16::checksum(&bytes[..124]));
+
+    // with a bad crc and a version 2 header, it is not MacBinary at all
+    let mut damaged = bytes.clone();
+    damaged[82] = 1;
+    assert!(matches!(
+        MacBinary::parse(&damaged),
+        Err(MacBinaryError::NotMacBinary)
+    ));
+}
+
+#[test]
+fn converts_to_and_from_hqx() {
+    let hqx = common::text_file(b"hello, world", &[1; 300]);
+
+    let bytes = hqx.borrow().to_macbinary().unwrap();
+    let macbinary = MacBinary::parse(&bytes).unwrap();
+    assert_eq!(macbinary.version, MacBinaryVersion::III);
+    assert_eq!(macbinary.name, b"Read Me");
+    assert_eq!(macbinary.to_hqx().unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn rejects_truncated_files_and_bad_names() {
+    let bytes = sample(MacBinaryVersion::III).to_bytes().unwrap();
+    assert!(matches!(
+        MacBinary::parse(&bytes[..bytes.len() - 200]),
+        Err(MacBinaryError::Truncated)
+    ));
+    assert!(matches!(
+        MacBinary::parse(&bytes[..100]),
+        Err(MacBinaryError::NotMacBinary)
+    ));
+
+    let mut unnamed = sample(MacBinaryVersion::III);
+    unnamed.name = b"";
+    assert!(matches!(unnamed.to_bytes(), Err(MacBinaryError::BadName)));
+}
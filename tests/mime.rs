@@ -0,0 +1,87 @@
+#![cfg(feature = "mime")]
+
+use binhex4::{
+    error::DecodeError,
+    mime::{extract_mbox, extract_message},
+};
+
+mod common;
+
+// escapes every `A` and breaks each line in two with a soft line break
+fn quoted_printable(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let (first, second) = line.split_at(line.len() / 2);
+            format!("{}=\r\n{}", first, second).replace('A', "=41")
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+fn nested(name: &str, depth: usize, innermost: &str) -> String {
+    (0..depth).fold(innermost.to_string(), |inner, level| {
+        format!(
+            "Content-Type: multipart/mixed; boundary=\"{name}{level}\"\r\n\r\n\
+             --{name}{level}\r\n{inner}\r\n--{name}{level}--\r\n"
+        )
+    })
+}
+
+#[test]
+fn decodes_quoted_printable_attachments() {
+    let hqx = common::sample();
+    let encoded = String::from_utf8(hqx.borrow().encode()).unwrap();
+    let message = format!(
+        "Message-ID: <1@example.com>\r\nContent-Type: text/plain\r\n\
+         Content-Transfer-Encoding: quoted-printable\r\n\r\n{}\r\n",
+        quoted_printable(&encoded)
+    );
+
+    let attachments = extract_message(message.as_bytes(), true);
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(
+        attachments[0].message_id.as_deref(),
+        Some("<1@example.com>")
+    );
+    assert_eq!(attachments[0].hqx.as_ref().unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn walks_nested_multiparts_and_messages() {
+    let hqx = common::sample();
+    let encoded = String::from_utf8(hqx.borrow().encode()).unwrap();
+    let attachment = format!(
+        "Content-Type: application/mac-binhex40; name=\"other.hqx\"\r\n\
+         Content-Disposition: attachment; filename=\"Read Me.hqx\"\r\n\r\n{encoded}"
+    );
+    let forwarded = format!(
+        "Content-Type: message/rfc822\r\n\r\nMessage-ID: <2@example.com>\r\n{}",
+        nested("inner", 3, &attachment)
+    );
+    let message = format!(
+        "From someone\r\nMessage-ID: <1@example.com>\r\n{}\r\n",
+        nested("outer", 2, &forwarded)
+    );
+
+    let attachments = extract_mbox(message.as_bytes(), true).collect::<Vec<_>>();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(
+        attachments[0].message_id.as_deref(),
+        Some("<2@example.com>")
+    );
+    assert_eq!(attachments[0].file_name.as_deref(), Some("Read Me.hqx"));
+    assert_eq!(attachments[0].hqx.as_ref().unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let encoded = String::from_utf8(common::sample().borrow().encode()).unwrap();
+    let attachment = format!("Content-Type: application/mac-binhex40\r\n\r\n{encoded}");
+
+    let attachments = extract_message(nested("b", 10_000, &attachment).as_bytes(), true);
+    assert_eq!(attachments.len(), 1);
+    assert!(matches!(
+        attachments[0].hqx,
+        Err(DecodeError::NestedTooDeeply)
+    ));
+}
@@ -0,0 +1,109 @@
+use binhex4::{
+    multipart::{reassemble, reassemble_concatenated},
+    HQX,
+};
+
+mod common;
+
+// bytes without runs, enough for several parts
+fn varied() -> Vec<u8> {
+    (0..4000).map(|n| (n * 7 % 251) as u8).collect()
+}
+
+// posts the encoded file in `count` parts, each with mail headers, a part
+// line and a signature around its share of the encoded lines
+fn split(hqx: &HQX, count: usize) -> Vec<String> {
+    let encoded = String::from_utf8(hqx.borrow().encode()).unwrap();
+    let lines: Vec<&str> = encoded.lines().collect();
+    let per_part = lines.len().div_ceil(count);
+    lines
+        .chunks(per_part)
+        .enumerate()
+        .map(|(index, chunk)| {
+            format!(
+                "From: poster@example.com\nSubject: fill.pict.hqx ({}/{})\n\n\
+                 ---- cut here ----\n{}\n---- cut here ----\n-- \nposter\n",
+                index + 1,
+                count,
+                chunk.join("\n"),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn reassembles_parts_in_order() {
+    let hqx = common::text_file(&varied(), &[]);
+    let mut parts = split(&hqx, 3);
+    parts.swap(0, 2);
+
+    let reassembly = reassemble(&parts);
+    assert!(reassembly.is_complete());
+    assert_eq!(reassembly.out_of_order, [2, 1]);
+    assert!(reassembly.duplicates.is_empty());
+    assert_eq!(reassembly.decode(true).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn keeps_lines_of_one_repeated_character() {
+    // a solid fill, whose three-byte period encodes to one character
+    let hqx = common::text_file(&[0x04, 0x10, 0x41].repeat(2000), &[]);
+    let encoded = String::from_utf8(hqx.borrow().encode()).unwrap();
+    assert!(encoded
+        .lines()
+        .any(|line| line.len() > 1 && line.bytes().all(|b| b == line.as_bytes()[0])));
+
+    let reassembly = reassemble(&split(&hqx, 2));
+    assert!(reassembly.is_complete());
+    assert_eq!(reassembly.decode(true).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn reports_missing_and_duplicate_parts() {
+    let hqx = common::text_file(&varied(), &[]);
+    let parts = split(&hqx, 3);
+
+    let reassembly = reassemble(&[&parts[0], &parts[0], &parts[2]]);
+    assert!(!reassembly.is_complete());
+    assert_eq!(reassembly.missing, [2]);
+    assert_eq!(reassembly.duplicates, [1]);
+}
+
+#[test]
+fn splits_concatenated_posts() {
+    let hqx = common::text_file(&varied(), &[]);
+    let mailbox = split(&hqx, 3).concat();
+
+    let reassembly = reassemble_concatenated(mailbox.as_bytes());
+    assert!(reassembly.is_complete());
+    let numbers: Vec<_> = reassembly.parts.iter().map(|part| part.number).collect();
+    assert_eq!(numbers, [Some(1), Some(2), Some(3)]);
+    assert_eq!(reassembly.decode(true).unwrap().vec, hqx.vec);
+}
+
+#[test]
+fn huge_part_counts_stay_cheap() {
+    // more parts than the input has bytes is not a part header at all
+    for header in ["part 1 of 400000000", "part 4294967295 of 4294967295"] {
+        let part = format!("{header}\n:abc\n");
+        for reassembly in [
+            reassemble(&[&part]),
+            reassemble_concatenated(part.as_bytes()),
+        ] {
+            assert_eq!(reassembly.parts[0].number, None);
+            assert!(reassembly.missing.is_empty());
+        }
+    }
+
+    // a believable count still lists only so many missing parts
+    let hqx = common::text_file(&varied(), &[]);
+    let parts: Vec<_> = split(&hqx, 3)
+        .iter()
+        .map(|part| part.replace("/3)", "/5000)"))
+        .collect();
+    let reassembly = reassemble(&parts);
+    assert!(!reassembly.is_complete());
+    assert_eq!(reassembly.parts[0].total, Some(5000));
+    assert_eq!(reassembly.missing.len(), 256 + 3);
+    assert_eq!(reassembly.missing[..3], [4, 5, 6]);
+}
@@ -0,0 +1,98 @@
+use std::io::Read;
+
+use binhex4::{decode::hexbin, reader::HexbinReader, HQX};
+
+mod common;
+
+fn sample() -> HQX {
+    let data: Vec<u8> = (0..3000).map(|n| (n % 7) as u8).collect();
+    common::text_file(&data, &[1, 2, 3])
+}
+
+fn read_all(input: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut reader = HexbinReader::new(input).unwrap();
+    let mut data = Vec::new();
+    reader.data_fork().read_to_end(&mut data).unwrap();
+    let mut resource = Vec::new();
+    reader
+        .resource_fork()
+        .unwrap()
+        .read_to_end(&mut resource)
+        .unwrap();
+    (data, resource)
+}
+
+#[test]
+fn finds_the_data_like_hexbin() {
+    let hqx = sample();
+    let hqx_ref = hqx.borrow();
+    let encoded = String::from_utf8(hqx_ref.encode()).unwrap();
+    let bare = encoded.replace("(This file must be converted with BinHex 4.0)", "");
+
+    for input in [
+        // a smiley at the start of a line before the marker
+        format!("From: a\nSubject: b\n\n:-) enjoy\n\n{encoded}"),
+        format!("From: a\r\rSee below:\r{encoded}"),
+        // no marker, so the first line of encoded characters
+        format!("From: a\nSubject: b\n\n:-) enjoy\n{bare}"),
+        // nor any such line, so the first colon anywhere
+        format!("Here it is {}", bare.trim_start()),
+    ] {
+        let expected = hexbin(input.as_bytes(), true).unwrap();
+        assert_eq!(expected.vec, hqx.vec);
+
+        let (data, resource) = read_all(input.as_bytes());
+        assert_eq!(data, hqx_ref.data_fork.as_ref().unwrap().data);
+        assert_eq!(resource, [1, 2, 3]);
+    }
+}
+
+// hands out its input a few bytes at a time, so that groups of characters
+// and runs are split across reads
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(3).min(self.0.len());
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn decodes_input_that_arrives_in_pieces() {
+    let hqx = sample();
+    let encoded = hqx.borrow().encode();
+
+    let mut reader = HexbinReader::new(Trickle(&encoded)).unwrap();
+    let mut data = Vec::new();
+    reader.data_fork().read_to_end(&mut data).unwrap();
+    assert_eq!(data, hqx.borrow().data_fork.as_ref().unwrap().data);
+}
+
+#[test]
+fn empty_reads_return_zero() {
+    let hqx = sample();
+    let encoded = hqx.borrow().encode();
+
+    let mut reader = HexbinReader::new(&encoded[..]).unwrap();
+    let mut fork = reader.data_fork();
+    assert_eq!(fork.read(&mut []).unwrap(), 0);
+    let mut data = Vec::new();
+    fork.read_to_end(&mut data).unwrap();
+    assert_eq!(data.len(), 3000);
+}
+
+#[test]
+fn illegal_characters_are_found_where_hexbin_finds_them() {
+    let encoded = String::from_utf8(sample().borrow().encode()).unwrap();
+    let line = encoded.lines().nth(10).unwrap();
+    let damaged = encoded.replacen(line, &format!("{}~{}", &line[..20], &line[21..]), 1);
+
+    let expected = hexbin(damaged.as_bytes(), true).unwrap_err().to_string();
+    let mut reader = HexbinReader::new(damaged.as_bytes()).unwrap();
+    let mut data = Vec::new();
+    let err = reader.data_fork().read_to_end(&mut data).unwrap_err();
+    assert_eq!(err.to_string(), expected);
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+
+use binhex4::{
+    error::ResourceError,
+    fourcc::FourCharCode,
+    resource::{Resource, ResourceFork},
+    HQX,
+};
+
+fn sample() -> ResourceFork<'static> {
+    let mut named = Resource::new((*b"STR ").into(), 128, &b"\x05hello"[..]);
+    named.name = Some(Cow::Borrowed(b"Greeting"));
+    named.attributes = 0x20;
+
+    ResourceFork {
+        attributes: 0x0080,
+        resources: vec![
+            named,
+            Resource::new((*b"STR ").into(), -16396, &b"\x03bye"[..]),
+            Resource::new((*b"ICN#").into(), 128, vec![0xAA; 256]),
+            Resource::new((*b"vers").into(), 1, Vec::new()),
+        ],
+    }
+}
+
+#[test]
+fn parses_what_it_builds() {
+    let fork = sample();
+    let bytes = fork.to_bytes().unwrap();
+    let parsed = ResourceFork::parse(&bytes).unwrap();
+    assert_eq!(parsed, fork);
+    assert_eq!(parsed.to_bytes().unwrap(), bytes);
+
+    assert_eq!(
+        parsed.types(),
+        [b"STR ", b"ICN#", b"vers"].map(|code| FourCharCode(*code))
+    );
+    let greeting = parsed.get((*b"STR ").into(), 128).unwrap();
+    assert_eq!(greeting.name.as_deref(), Some(&b"Greeting"[..]));
+    assert_eq!(parsed.of_type((*b"STR ").into()).count(), 2);
+}
+
+#[test]
+fn parses_forks_decoded_from_hqx() {
+    let bytes = sample().to_bytes().unwrap();
+    let hqx = HQX::builder()
+        .name("Resources")
+        .resource_fork(bytes)
+        .build()
+        .unwrap();
+
+    assert_eq!(hqx.borrow().resources().unwrap(), sample());
+    let empty = HQX::builder().name("Empty").build().unwrap();
+    assert!(empty.borrow().resources().unwrap().resources.is_empty());
+}
+
+#[test]
+fn empty_forks_round_trip() {
+    let bytes = ResourceFork::default().to_bytes().unwrap();
+    assert_eq!(
+        ResourceFork::parse(&bytes).unwrap(),
+        ResourceFork::default()
+    );
+}
+
+// a fork whose `type_count` type entries all share one list of `ref_count`
+// refs to a single empty resource
+fn shared_ref_list(type_count: usize, ref_count: usize) -> Vec<u8> {
+    const DATA_START: usize = 256;
+    let map_offset = DATA_START + 4;
+    let type_list_len = 2 + type_count * 8;
+    let map_len = 28 + type_list_len + ref_count * 12;
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&(DATA_START as u32).to_be_bytes());
+    header.extend_from_slice(&(map_offset as u32).to_be_bytes());
+    header.extend_from_slice(&4u32.to_be_bytes());
+    header.extend_from_slice(&(map_len as u32).to_be_bytes());
+
+    let mut bytes = header.clone();
+    bytes.resize(DATA_START, 0);
+    bytes.extend_from_slice(&0u32.to_be_bytes());
+
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&[0; 6 + 2]);
+    bytes.extend_from_slice(&28u16.to_be_bytes());
+    bytes.extend_from_slice(&(map_len as u16).to_be_bytes());
+    bytes.extend_from_slice(&(type_count as u16 - 1).to_be_bytes());
+    for n in 0..type_count {
+        bytes.extend_from_slice(&(n as u32).to_be_bytes());
+        bytes.extend_from_slice(&(ref_count as u16 - 1).to_be_bytes());
+        bytes.extend_from_slice(&(type_list_len as u16).to_be_bytes());
+    }
+    for id in 0..ref_count {
+        bytes.extend_from_slice(&(id as u16).to_be_bytes());
+        bytes.extend_from_slice(&0xFFFFu16.to_be_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+    }
+    bytes
+}
+
+#[test]
+fn rejects_ref_lists_shared_beyond_the_map() {
+    let bytes = shared_ref_list(1, 100);
+    let fork = ResourceFork::parse(&bytes).unwrap();
+    assert_eq!(fork.resources.len(), 100);
+
+    assert!(matches!(
+        ResourceFork::parse(&shared_ref_list(100, 1000)),
+        Err(ResourceError::TooManyResources)
+    ));
+}
+
+#[test]
+fn rejects_truncated_forks() {
+    let bytes = sample().to_bytes().unwrap();
+    assert!(matches!(
+        ResourceFork::parse(&bytes[..8]),
+        Err(ResourceError::TruncatedHeader)
+    ));
+    assert!(ResourceFork::parse(&bytes[..bytes.len() - 20]).is_err());
+}
@@ -0,0 +1,51 @@
+use binhex4::{decode::DecodeMode, parse::locate, sixbit};
+
+mod common;
+
+// the run-length compressed stream that `encode` wrote for a file with
+// `data` as its data fork
+fn compressed(data: &[u8]) -> Vec<u8> {
+    let encoded = common::text_file(data, b"").borrow().encode();
+    let payload = locate(&encoded).unwrap();
+    sixbit::decode(&sixbit::strip(payload.encoded, DecodeMode::Strict)).unwrap()
+}
+
+// whether `data`, between an `x` and a `y` so that no run crosses into the
+// rest of the file, was compressed to `expected`
+fn compresses_to(data: &[u8], expected: &[u8]) -> bool {
+    let needle = [&b"x"[..], expected, b"y"].concat();
+    compressed(&[&b"x"[..], data, b"y"].concat())
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[test]
+fn escapes_a_literal_marker_byte() {
+    assert!(compresses_to(b"\x90", b"\x90\x00"));
+}
+
+#[test]
+fn leaves_runs_of_two_as_they_are() {
+    assert!(compresses_to(b"AA", b"AA"));
+    assert!(compresses_to(b"AAA", b"A\x90\x03"));
+}
+
+#[test]
+fn splits_long_runs_at_255() {
+    assert!(compresses_to(&[b'A'; 300], b"A\x90\xFFA\x90\x2D"));
+    assert!(compresses_to(&[b'A'; 256], b"A\x90\xFFA"));
+}
+
+#[test]
+fn compresses_runs_of_the_marker_byte() {
+    assert!(compresses_to(b"\x90\x90", b"\x90\x00\x90\x02"));
+    assert!(compresses_to(&[0x90; 5], b"\x90\x00\x90\x05"));
+}
+
+#[test]
+fn long_runs_encode_smaller_than_their_input() {
+    let data = vec![0; 10_000];
+    let encoded = common::text_file(&data, b"").borrow().encode();
+    assert!(encoded.len() < data.len() / 10);
+    assert!(compressed(&data).len() < 200);
+}
@@ -0,0 +1,263 @@
+use std::{
+    ffi::CString,
+    fs,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use binhex4::{
+    decode::{hexbin, DecodeMode},
+    encode::EncodeOptions,
+    layout::hexbin_with_layout,
+    reader::HexbinReader,
+    writer::BinhexWriter,
+    HQXConfig, HQXHeader, HQXRef, HQX,
+};
+
+mod common;
+
+fn fork_data<'a>(fork: &Option<binhex4::Fork<'a>>) -> &'a [u8] {
+    fork.as_ref().map(|fork| fork.data).unwrap_or_default()
+}
+
+fn fork_crc(fork: &Option<binhex4::Fork>) -> Option<u16> {
+    fork.as_ref().map(|fork| fork.crc)
+}
+
+fn assert_same_file(name: &str, expected: &HQXRef, actual: &HQXRef) {
+    assert_eq!(expected.name, actual.name, "{name}: name");
+    assert_eq!(expected.file_type, actual.file_type, "{name}: type");
+    assert_eq!(expected.author, actual.author, "{name}: creator");
+    assert_eq!(expected.flags, actual.flags, "{name}: flags");
+    assert_eq!(expected.data_len, actual.data_len, "{name}: data length");
+    assert_eq!(
+        expected.resource_len, actual.resource_len,
+        "{name}: resource length"
+    );
+    assert_eq!(expected.hc, actual.hc, "{name}: header crc");
+    assert_eq!(
+        fork_data(&expected.data_fork),
+        fork_data(&actual.data_fork),
+        "{name}: data fork"
+    );
+    assert_eq!(
+        fork_crc(&expected.data_fork),
+        fork_crc(&actual.data_fork),
+        "{name}: data crc"
+    );
+    assert_eq!(
+        fork_data(&expected.resource_fork),
+        fork_data(&actual.resource_fork),
+        "{name}: resource fork"
+    );
+    assert_eq!(
+        fork_crc(&expected.resource_fork),
+        fork_crc(&actual.resource_fork),
+        "{name}: resource crc"
+    );
+}
+
+fn header_of(hqx_ref: &HQXRef) -> HQXHeader {
+    HQXHeader {
+        name: hqx_ref.name.into(),
+        file_type: hqx_ref.file_type,
+        author: hqx_ref.author,
+        flags: hqx_ref.flags,
+        data_len: hqx_ref.data_len,
+        resource_len: hqx_ref.resource_len,
+        hc: hqx_ref.hc,
+    }
+}
+
+#[test]
+fn encode_then_decode_keeps_every_file() {
+    for (name, bytes) in common::test_files() {
+        let hqx = hexbin(&bytes, true).unwrap_or_else(|err| panic!("{name}: {err}"));
+        let original = hqx.borrow();
+
+        let encoded = original.encode();
+        let decoded = hexbin(&encoded, true).unwrap_or_else(|err| panic!("{name}: {err}"));
+
+        assert_same_file(&name, &original, &decoded.borrow());
+    }
+}
+
+#[test]
+fn from_config_rebuilds_every_file() {
+    for (name, bytes) in common::test_files() {
+        let hqx = hexbin(&bytes, true).unwrap();
+        let original = hqx.borrow();
+
+        let rebuilt = HQX::from_config(HQXConfig {
+            name: Some(original.name.into()),
+            file_type: Some(original.file_type),
+            author: Some(original.author),
+            flags: Some(original.flags),
+            data: Some(fork_data(&original.data_fork)),
+            resource: Some(fork_data(&original.resource_fork)),
+        })
+        .unwrap();
+        let encoded = rebuilt.borrow().encode();
+        let decoded = hexbin(&encoded, true).unwrap_or_else(|err| panic!("{name}: {err}"));
+        let decoded = decoded.borrow();
+
+        // flags that describe the encoding machine are cleared on encode
+        assert_eq!(decoded.flags, original.flags.sanitized(), "{name}: flags");
+        if original.flags == original.flags.sanitized() {
+            assert_same_file(&name, &original, &decoded);
+        } else {
+            assert_eq!(
+                fork_data(&original.data_fork),
+                fork_data(&decoded.data_fork)
+            );
+            assert_eq!(
+                fork_data(&original.resource_fork),
+                fork_data(&decoded.resource_fork)
+            );
+        }
+    }
+}
+
+#[test]
+fn layout_reproduces_every_file() {
+    for (name, bytes) in common::test_files() {
+        let (hqx, layout) = hexbin_with_layout(&bytes, true, DecodeMode::Lenient).unwrap();
+        let encoded = hqx.borrow().encode_with_layout(&layout);
+        assert!(encoded == bytes, "{name}: layout round trip differs");
+    }
+}
+
+#[test]
+fn reader_and_writer_match_whole_buffer_api() {
+    for (name, bytes) in common::test_files() {
+        let hqx = hexbin(&bytes, true).unwrap();
+        let original = hqx.borrow();
+
+        let mut reader = HexbinReader::new(&bytes[..]).unwrap();
+        let mut data = Vec::new();
+        reader.data_fork().read_to_end(&mut data).unwrap();
+        let mut resource = Vec::new();
+        reader
+            .resource_fork()
+            .unwrap()
+            .read_to_end(&mut resource)
+            .unwrap();
+        assert_eq!(data, fork_data(&original.data_fork), "{name}: data fork");
+        assert_eq!(
+            resource,
+            fork_data(&original.resource_fork),
+            "{name}: resource fork"
+        );
+
+        let mut writer = BinhexWriter::new(Vec::new(), header_of(&original)).unwrap();
+        writer.write_all(&data).unwrap();
+        writer.write_all(&resource).unwrap();
+        let written = writer.finish().unwrap();
+        let decoded = hexbin(&written, true).unwrap();
+
+        // both clear the flags that describe the encoding machine
+        let rebuilt = HQX::from_config(HQXConfig {
+            name: Some(original.name.into()),
+            file_type: Some(original.file_type),
+            author: Some(original.author),
+            flags: Some(original.flags),
+            data: Some(&data),
+            resource: Some(&resource),
+        })
+        .unwrap();
+        assert_same_file(&name, &rebuilt.borrow(), &decoded.borrow());
+    }
+}
+
+#[test]
+fn resource_only_file_round_trips() {
+    let bytes =
+        fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/hex/THINKC.HQX")).unwrap();
+    let hqx = hexbin(&bytes, true).unwrap();
+    let original = hqx.borrow();
+    assert!(original.data_fork.is_none());
+    assert!(original.resource_len > 0);
+
+    let decoded = hexbin(&original.encode(), true).unwrap();
+    let decoded = decoded.borrow();
+    assert!(decoded.data_fork.is_none());
+    assert_same_file("THINKC.HQX", &original, &decoded);
+}
+
+#[test]
+fn empty_forks_round_trip() {
+    let cases: [(&[u8], &[u8]); 4] = [
+        (b"", b""),
+        (b"data only", b""),
+        (b"", b"resource only"),
+        (b"\x90\x90\x90\x00\x00\x00\x00", b"\x90"),
+    ];
+
+    for (data, resource) in cases {
+        let hqx = HQX::from_config(HQXConfig {
+            name: Some(CString::new("empty").unwrap()),
+            file_type: None,
+            author: None,
+            flags: None,
+            data: Some(data),
+            resource: Some(resource),
+        })
+        .unwrap();
+        let original = hqx.borrow();
+
+        let decoded = hexbin(&original.encode(), true).unwrap();
+        let decoded = decoded.borrow();
+        assert_same_file("empty", &original, &decoded);
+        assert_eq!(decoded.data_fork.is_none(), data.is_empty());
+        assert_eq!(decoded.resource_fork.is_none(), resource.is_empty());
+    }
+}
+
+#[test]
+fn writer_breaks_lines_like_encode_with() {
+    let hqx = HQX::builder()
+        .name("lines")
+        .data_fork(&b"enough data for a few lines"[..])
+        .build()
+        .unwrap();
+    let original = hqx.borrow();
+
+    for line_length in 0..6 {
+        for short_first_line in [false, true] {
+            let options = EncodeOptions {
+                line_length,
+                short_first_line,
+                ..EncodeOptions::default()
+            };
+            let expected = original.encode_with(&options);
+
+            let mut writer =
+                BinhexWriter::with_options(Vec::new(), header_of(&original), options).unwrap();
+            writer.write_all(fork_data(&original.data_fork)).unwrap();
+            let written = writer.finish().unwrap();
+            assert_eq!(
+                String::from_utf8(written).unwrap(),
+                String::from_utf8(expected).unwrap(),
+                "line length {line_length}, short first line {short_first_line}"
+            );
+        }
+    }
+}
+
+#[test]
+fn writer_computes_the_header_crc() {
+    let hqx = common::text_file(b"hello", &[1, 2, 3]);
+    let original = hqx.borrow();
+    let header = HQXHeader {
+        hc: original.hc ^ 0xFFFF,
+        ..header_of(&original)
+    };
+
+    let mut writer = BinhexWriter::new(Vec::new(), header).unwrap();
+    assert_eq!(writer.header().hc, original.hc);
+    writer.write_all(b"hello").unwrap();
+    writer.write_all(&[1, 2, 3]).unwrap();
+    let written = writer.finish().unwrap();
+
+    assert_eq!(written, original.encode());
+}
@@ -0,0 +1,106 @@
+// the simd paths only run with `--features simd`; without it these check the
+// bulk scalar code against itself and against the streaming decoder
+use binhex4::{
+    decode::{hexbin, hexbin_with_mode, DecodeMode},
+    encode::{EncodeOptions, LineEnding},
+    error::DecodeError,
+    sixbit, HQXConfig, HQX,
+};
+use proptest::prelude::*;
+
+const ALPHABET: &[u8] = b"!\"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr";
+
+fn encoded_chars() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(prop::sample::select(ALPHABET), 0..400)
+}
+
+proptest! {
+    #[test]
+    fn decode_matches_scalar(chars in encoded_chars()) {
+        prop_assert_eq!(sixbit::decode(&chars), sixbit::decode_scalar(&chars));
+    }
+
+    #[test]
+    fn decode_reports_the_same_invalid_character(
+        mut chars in encoded_chars(),
+        bad in any::<u8>().prop_filter("outside the alphabet", |b| !ALPHABET.contains(b)),
+        at in any::<prop::sample::Index>(),
+    ) {
+        let at = at.index(chars.len() + 1);
+        chars.insert(at, bad);
+        prop_assert_eq!(sixbit::decode(&chars), Err(at));
+        prop_assert_eq!(sixbit::decode_scalar(&chars), Err(at));
+    }
+
+    #[test]
+    fn decode_matches_scalar_on_any_bytes(bytes in prop::collection::vec(any::<u8>(), 0..200)) {
+        prop_assert_eq!(sixbit::decode(&bytes), sixbit::decode_scalar(&bytes));
+    }
+
+    #[test]
+    fn encode_matches_scalar(bytes in prop::collection::vec(any::<u8>(), 0..400)) {
+        let chars = sixbit::encode(&bytes);
+        prop_assert_eq!(&chars, &sixbit::encode_scalar(&bytes));
+        prop_assert_eq!(sixbit::decode(&chars), Ok(bytes));
+    }
+
+    #[test]
+    fn whole_files_round_trip(
+        data in prop::collection::vec(any::<u8>(), 0..600),
+        resource in prop::collection::vec(any::<u8>(), 0..200),
+        line_length in 0usize..80,
+        line_ending in prop::sample::select(&[LineEnding::Cr, LineEnding::Lf, LineEnding::CrLf][..]),
+    ) {
+        let hqx = HQX::from_config(HQXConfig {
+            name: None,
+            file_type: None,
+            author: None,
+            flags: None,
+            data: Some(&data),
+            resource: Some(&resource),
+        })
+        .unwrap();
+        let options = EncodeOptions {
+            line_length,
+            line_ending,
+            ..EncodeOptions::default()
+        };
+        let encoded = hqx.borrow().encode_with(&options);
+
+        let decoded = hexbin_with_mode(&encoded, true, DecodeMode::Strict).unwrap();
+        prop_assert_eq!(&decoded.vec, &hqx.vec);
+    }
+}
+
+#[test]
+fn illegal_character_keeps_its_position() {
+    let data: Vec<u8> = (0..=255).collect();
+    let hqx = HQX::from_config(HQXConfig {
+        name: None,
+        file_type: None,
+        author: None,
+        flags: None,
+        data: Some(&data),
+        resource: None,
+    })
+    .unwrap();
+    let mut encoded = hqx.borrow().encode();
+
+    // the first character of the fifth line, after the marker, a blank line
+    // and two lines of encoded characters
+    let at = encoded
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair == b"\r\n")
+        .nth(3)
+        .map(|(at, _)| at + 2)
+        .unwrap();
+    encoded[at] = b'~';
+
+    match hexbin(&encoded, true) {
+        Err(DecodeError::IllegalCharacter { byte, line, column }) => {
+            assert_eq!((byte, line, column), (b'~', 5, 1));
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+}
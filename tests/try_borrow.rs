@@ -0,0 +1,133 @@
+use binhex4::{
+    decode::hexbin,
+    error::{DecodeError, ParseError},
+    sixbit, ForkKind, HQX,
+};
+
+mod common;
+
+// `Read Me` makes a 29 byte header, followed by 5 bytes of data fork and its
+// crc, then 3 bytes of resource fork and its crc
+const HEADER_LEN: usize = 29;
+
+fn sample() -> HQX {
+    common::text_file(b"hello", &[1, 2, 3])
+}
+
+fn truncated(len: usize) -> HQX {
+    HQX::new(sample().vec[..len].to_vec())
+}
+
+#[test]
+fn accepts_buffers_from_this_crate() {
+    let hqx = sample();
+    assert_eq!(hqx.vec.len(), HEADER_LEN + 5 + 2 + 3 + 2);
+    let hqx_ref = hqx.try_borrow().unwrap();
+    assert_eq!(hqx_ref.data_fork.unwrap().data, b"hello");
+    assert_eq!(hqx_ref.resource_fork.unwrap().data, [1, 2, 3]);
+}
+
+#[test]
+fn truncated_headers() {
+    assert!(matches!(
+        HQX::new(Vec::new()).try_borrow(),
+        Err(ParseError::TruncatedHeader {
+            len: 0,
+            expected: 22
+        })
+    ));
+    assert!(matches!(
+        truncated(HEADER_LEN - 1).try_borrow(),
+        Err(ParseError::TruncatedHeader {
+            len: 28,
+            expected: HEADER_LEN
+        })
+    ));
+}
+
+#[test]
+fn bad_names() {
+    // a null byte inside the name
+    let mut hqx = sample();
+    hqx.vec[3] = 0;
+    assert!(matches!(
+        hqx.try_borrow(),
+        Err(ParseError::BadName { offset: 1 })
+    ));
+
+    // no null byte after it
+    let mut hqx = sample();
+    hqx.vec[8] = b'!';
+    assert!(matches!(
+        hqx.try_borrow(),
+        Err(ParseError::BadName { offset: 1 })
+    ));
+}
+
+#[test]
+fn forks_out_of_bounds() {
+    assert!(matches!(
+        truncated(HEADER_LEN + 3).try_borrow(),
+        Err(ParseError::ForkOutOfBounds {
+            fork: ForkKind::Data,
+            offset: HEADER_LEN,
+            len: 5,
+            available: 3
+        })
+    ));
+    assert!(matches!(
+        truncated(HEADER_LEN + 7 + 1).try_borrow(),
+        Err(ParseError::ForkOutOfBounds {
+            fork: ForkKind::Resource,
+            offset: 36,
+            len: 3,
+            available: 1
+        })
+    ));
+
+    // lengths near u32::MAX must not overflow the offsets
+    let mut hqx = sample();
+    hqx.vec[HEADER_LEN - 10..HEADER_LEN - 6].copy_from_slice(&[0xFF; 4]);
+    assert!(matches!(
+        hqx.try_borrow(),
+        Err(ParseError::ForkOutOfBounds {
+            fork: ForkKind::Data,
+            len: u32::MAX,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn missing_crcs() {
+    assert!(matches!(
+        truncated(HEADER_LEN + 5 + 1).try_borrow(),
+        Err(ParseError::MissingCRC {
+            fork: ForkKind::Data,
+            offset: 34
+        })
+    ));
+    assert!(matches!(
+        truncated(HEADER_LEN + 7 + 3).try_borrow(),
+        Err(ParseError::MissingCRC {
+            fork: ForkKind::Resource,
+            offset: 39
+        })
+    ));
+}
+
+#[test]
+fn hexbin_checks_bounds_without_verifying() {
+    let hqx = truncated(HEADER_LEN + 3);
+    // no marker bytes, so the stream needs no run-length encoding
+    assert!(!hqx.vec.contains(&0x90));
+
+    let encoded = [&b":"[..], &sixbit::encode(&hqx.vec), b":"].concat();
+    assert!(matches!(
+        hexbin(&encoded, false),
+        Err(DecodeError::Parse(ParseError::ForkOutOfBounds {
+            fork: ForkKind::Data,
+            ..
+        }))
+    ));
+}